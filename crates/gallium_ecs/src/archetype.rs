@@ -0,0 +1,278 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{PoisonError, RwLock},
+};
+
+use crate::{Component, ComponentRef, ComponentTicks, EntityId};
+
+/** A type-erased component along with its change ticks */
+pub(crate) type TickedComponent = (Box<dyn Component>, ComponentTicks);
+
+/** A type-erased column storing components of a single type */
+pub trait Column: Send + Sync {
+    /** Get column as any */
+    fn as_any(&self) -> &dyn Any;
+    /** Get column as mutable any */
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /** Get the type-id of the stored components */
+    fn component_type(&self) -> TypeId;
+    /** Get the type name of the stored components */
+    fn component_name(&self) -> &'static str;
+    /** Construct an empty column storing the same component type */
+    fn new_empty(&self) -> Box<dyn Column>;
+    /** Push a boxed component and its ticks to the end of the column */
+    fn push_boxed(&mut self, component: Box<dyn Component>, ticks: ComponentTicks);
+    /** Remove the component in the row, replacing it with the last component */
+    fn swap_remove_boxed(&mut self, row: usize) -> (Box<dyn Component>, ComponentTicks);
+    /** Borrow the component in the row as a component */
+    fn try_get_dyn(&self, row: usize) -> Option<ComponentRef<'_>>;
+}
+
+/** A contiguous column of components of type T */
+pub struct TypedColumn<T> {
+    // The components, indexed by archetype row
+    pub(crate) data: Vec<RwLock<T>>,
+    // The change ticks of the components
+    pub(crate) ticks: Vec<ComponentTicks>,
+}
+
+impl<T: 'static + Component> Default for TypedColumn<T> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl<T: 'static + Component> TypedColumn<T> {
+    /** Construct an empty column */
+    pub fn new() -> Self {
+        return Self {
+            data: vec![],
+            ticks: vec![],
+        };
+    }
+
+    /** Get the component cell in the row */
+    pub fn get(&self, row: usize) -> Option<&RwLock<T>> {
+        return self.data.get(row);
+    }
+
+    /** Get the change ticks of the component in the row */
+    pub fn get_ticks(&self, row: usize) -> Option<&ComponentTicks> {
+        return self.ticks.get(row);
+    }
+}
+
+impl<T: 'static + Component> Column for TypedColumn<T> {
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        return self;
+    }
+
+    fn component_type(&self) -> TypeId {
+        return TypeId::of::<T>();
+    }
+
+    fn component_name(&self) -> &'static str {
+        return std::any::type_name::<T>();
+    }
+
+    fn new_empty(&self) -> Box<dyn Column> {
+        return Box::new(TypedColumn::<T>::new());
+    }
+
+    fn push_boxed(&mut self, component: Box<dyn Component>, ticks: ComponentTicks) {
+        let component = match component.into_any().downcast::<T>() {
+            Ok(c) => c,
+            Err(_) => panic!(
+                "Component pushed into column of the wrong type: {}",
+                std::any::type_name::<T>()
+            ),
+        };
+        self.data.push(RwLock::new(*component));
+        self.ticks.push(ticks);
+    }
+
+    fn swap_remove_boxed(&mut self, row: usize) -> (Box<dyn Component>, ComponentTicks) {
+        let component = self
+            .data
+            .swap_remove(row)
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        return (Box::new(component), self.ticks.swap_remove(row));
+    }
+
+    fn try_get_dyn(&self, row: usize) -> Option<ComponentRef<'_>> {
+        let lock = self.data.get(row)?;
+        return match lock.try_read() {
+            Ok(c) => Some(ComponentRef::new(c)),
+            Err(_) => None,
+        };
+    }
+}
+
+/** The location of an entity's components in the archetype tables */
+#[derive(Clone, Copy, Debug)]
+pub struct EntityLocation {
+    // The index of the archetype
+    pub archetype: usize,
+    // The row in the archetype
+    pub row: usize,
+}
+
+/** A table of entities that share the exact same set of component types */
+pub struct Archetype {
+    // The sorted component types
+    types: Vec<TypeId>,
+    // The component columns, in the same order as the types
+    columns: Vec<Box<dyn Column>>,
+    // The entity stored in each row
+    entities: Vec<EntityId>,
+}
+
+impl Archetype {
+    /** Construct an archetype from a set of empty columns */
+    pub(crate) fn new(mut columns: Vec<Box<dyn Column>>) -> Self {
+        columns.sort_by_key(|c| c.component_type());
+        return Self {
+            types: columns.iter().map(|c| c.component_type()).collect(),
+            columns,
+            entities: vec![],
+        };
+    }
+
+    /** Get the component types stored in the archetype */
+    pub fn types(&self) -> &[TypeId] {
+        return &self.types;
+    }
+
+    /** Get the entities stored in the archetype, indexed by row */
+    pub fn entities(&self) -> &[EntityId] {
+        return &self.entities;
+    }
+
+    /** Get the amount of entities stored in the archetype */
+    pub fn len(&self) -> usize {
+        return self.entities.len();
+    }
+
+    /** Check if the archetype stores no entities */
+    pub fn is_empty(&self) -> bool {
+        return self.entities.is_empty();
+    }
+
+    /** Check if the archetype stores components of the type */
+    pub fn contains(&self, component_type: TypeId) -> bool {
+        return self.types.binary_search(&component_type).is_ok();
+    }
+
+    /** Check if the archetype stores components of type T */
+    pub fn has_component<T: 'static + Component>(&self) -> bool {
+        return self.contains(TypeId::of::<T>());
+    }
+
+    /** Get the column storing components of type T */
+    pub fn get_column<T: 'static + Component>(&self) -> Option<&TypedColumn<T>> {
+        let index = self.types.binary_search(&TypeId::of::<T>()).ok()?;
+        return self.columns[index]
+            .as_any()
+            .downcast_ref::<TypedColumn<T>>();
+    }
+
+    /** Get the type-erased columns of the archetype */
+    pub fn columns(&self) -> &[Box<dyn Column>] {
+        return &self.columns;
+    }
+
+    /** Push an entity with its components sorted by type, returning the row */
+    pub(crate) fn push(&mut self, entity: EntityId, components: Vec<TickedComponent>) -> usize {
+        for (column, (component, ticks)) in self.columns.iter_mut().zip(components) {
+            column.push_boxed(component, ticks);
+        }
+        self.entities.push(entity);
+        return self.entities.len() - 1;
+    }
+
+    /**
+     * Remove the entity in the row, returning its components sorted by type.
+     * The last entity gets moved into the removed row, its id is returned if there was one.
+     */
+    pub(crate) fn swap_remove(&mut self, row: usize) -> (Vec<TickedComponent>, Option<EntityId>) {
+        let components = self
+            .columns
+            .iter_mut()
+            .map(|c| c.swap_remove_boxed(row))
+            .collect();
+        self.entities.swap_remove(row);
+        return (components, self.entities.get(row).copied());
+    }
+}
+
+/** Stores the archetypes of a scene */
+pub(crate) struct Archetypes {
+    // The archetypes
+    archetypes: Vec<Archetype>,
+    // Maps sorted component types to the index of their archetype
+    index: HashMap<Vec<TypeId>, usize>,
+}
+
+impl Archetypes {
+    /** Construct an empty set of archetypes */
+    pub(crate) fn new() -> Self {
+        return Self {
+            archetypes: vec![],
+            index: HashMap::new(),
+        };
+    }
+
+    /** Get an archetype by its index */
+    pub(crate) fn get(&self, index: usize) -> &Archetype {
+        return &self.archetypes[index];
+    }
+
+    /** Get a mutable archetype by its index */
+    pub(crate) fn get_mut(&mut self, index: usize) -> &mut Archetype {
+        return &mut self.archetypes[index];
+    }
+
+    /** Get all archetypes */
+    pub(crate) fn iter(&self) -> std::slice::Iter<'_, Archetype> {
+        return self.archetypes.iter();
+    }
+
+    /** Insert an entity's components, returning where they are stored */
+    pub(crate) fn insert(
+        &mut self,
+        entity: EntityId,
+        components: Vec<TickedComponent>,
+    ) -> EntityLocation {
+        // Remove duplicate component types, the last one added wins
+        let mut unique: Vec<TickedComponent> = vec![];
+        for component in components {
+            let component_type = component.0.as_any().type_id();
+            unique.retain(|c| c.0.as_any().type_id() != component_type);
+            unique.push(component);
+        }
+        // Sort the components to match the column order
+        unique.sort_by_key(|c| c.0.as_any().type_id());
+
+        // Find or create the archetype
+        let types: Vec<TypeId> = unique.iter().map(|c| c.0.as_any().type_id()).collect();
+        let archetype = match self.index.get(&types) {
+            Some(index) => *index,
+            None => {
+                let columns = unique.iter().map(|c| c.0.create_column()).collect();
+                self.archetypes.push(Archetype::new(columns));
+                self.index.insert(types, self.archetypes.len() - 1);
+                self.archetypes.len() - 1
+            }
+        };
+
+        // Push the components
+        let row = self.archetypes[archetype].push(entity, unique);
+        return EntityLocation { archetype, row };
+    }
+}
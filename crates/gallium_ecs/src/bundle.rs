@@ -0,0 +1,36 @@
+use crate::Component;
+
+/** A group of components that can be added to an entity at once */
+pub trait Bundle {
+    /** Move the components of the bundle into the list, in order */
+    fn take_components(self, components: &mut Vec<Box<dyn Component>>);
+}
+
+impl<T: 'static + Component> Bundle for T {
+    fn take_components(self, components: &mut Vec<Box<dyn Component>>) {
+        components.push(Box::new(self));
+    }
+}
+
+/** Implement bundle for a tuple of bundles */
+macro_rules! impl_bundle_tuple {
+    ($($name:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<$($name: Bundle),*> Bundle for ($($name,)*) {
+            fn take_components(self, _components: &mut Vec<Box<dyn Component>>) {
+                let ($($name,)*) = self;
+                $($name.take_components(_components);)*
+            }
+        }
+    };
+}
+
+impl_bundle_tuple!();
+impl_bundle_tuple!(A);
+impl_bundle_tuple!(A, B);
+impl_bundle_tuple!(A, B, C);
+impl_bundle_tuple!(A, B, C, D);
+impl_bundle_tuple!(A, B, C, D, E);
+impl_bundle_tuple!(A, B, C, D, E, F);
+impl_bundle_tuple!(A, B, C, D, E, F, G);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H);
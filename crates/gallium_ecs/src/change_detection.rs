@@ -0,0 +1,110 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLockWriteGuard,
+    },
+};
+
+/** The ticks at which a component was added and last changed */
+#[derive(Debug)]
+pub struct ComponentTicks {
+    // The tick the component was added at
+    added: AtomicU64,
+    // The tick the component was last mutably accessed at, atomic so systems can mark changes in parallel
+    changed: AtomicU64,
+}
+
+impl ComponentTicks {
+    /** Construct ticks for a component added at the tick */
+    pub fn new(tick: u64) -> Self {
+        return Self {
+            added: AtomicU64::new(tick),
+            changed: AtomicU64::new(tick),
+        };
+    }
+
+    /** Get the tick the component was added at */
+    pub fn added(&self) -> u64 {
+        return self.added.load(Ordering::Relaxed);
+    }
+
+    /** Get the tick the component was last changed at */
+    pub fn changed(&self) -> u64 {
+        return self.changed.load(Ordering::Relaxed);
+    }
+
+    /** Check if the component was added after the tick */
+    pub fn is_added(&self, last_run: u64) -> bool {
+        return self.added.load(Ordering::Relaxed) > last_run;
+    }
+
+    /** Check if the component was changed after the tick */
+    pub fn is_changed(&self, last_run: u64) -> bool {
+        return self.changed.load(Ordering::Relaxed) > last_run;
+    }
+
+    /** Mark the component as changed at the tick */
+    pub fn set_changed(&self, tick: u64) {
+        self.changed.store(tick, Ordering::Relaxed);
+    }
+}
+
+impl Clone for ComponentTicks {
+    fn clone(&self) -> Self {
+        return Self {
+            added: AtomicU64::new(self.added()),
+            changed: AtomicU64::new(self.changed()),
+        };
+    }
+}
+
+/** A mutable borrow of a component that marks it as changed when written to */
+pub struct Mut<'a, T> {
+    // The borrowed component
+    value: RwLockWriteGuard<'a, T>,
+    // The ticks of the component
+    ticks: &'a ComponentTicks,
+    // The tick to mark the component as changed at
+    change_tick: u64,
+}
+
+impl<'a, T> Mut<'a, T> {
+    /** Construct a mutable borrow of a component */
+    pub(crate) fn new(
+        value: RwLockWriteGuard<'a, T>,
+        ticks: &'a ComponentTicks,
+        change_tick: u64,
+    ) -> Self {
+        return Self {
+            value,
+            ticks,
+            change_tick,
+        };
+    }
+
+    /** Get the ticks of the component */
+    pub fn ticks(&self) -> &ComponentTicks {
+        return self.ticks;
+    }
+
+    /** Mark the component as changed without writing to it */
+    pub fn set_changed(&mut self) {
+        self.ticks.set_changed(self.change_tick);
+    }
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        return &self.value;
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.ticks.set_changed(self.change_tick);
+        return &mut self.value;
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{Bundle, Component, Entity, EntityId, Scene, World};
+
+/** A queued change to a scene or its world */
+type Command = Box<dyn FnOnce(&mut Scene, &mut World) + Send>;
+
+/** Queues structural changes to a scene, applied after the running system or at Scene::apply_commands */
+#[derive(Default)]
+pub struct Commands {
+    // The queued commands, in the order they were queued
+    queue: Vec<Command>,
+}
+
+impl Commands {
+    /** Construct an empty command buffer */
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /** Queue a custom change */
+    pub fn add<F: 'static + Send + FnOnce(&mut Scene, &mut World)>(&mut self, command: F) {
+        self.queue.push(Box::new(command));
+    }
+
+    /** Queue adding an entity to the scene */
+    pub fn spawn(&mut self, entity: Entity) {
+        self.add(move |scene, _| {
+            scene.add_entity(entity);
+        });
+    }
+
+    /** Queue despawning an entity */
+    pub fn despawn(&mut self, id: EntityId) {
+        self.add(move |scene, _| {
+            scene.despawn(id);
+        });
+    }
+
+    /** Queue despawning an entity along with its descendants */
+    pub fn despawn_recursive(&mut self, id: EntityId) {
+        self.add(move |scene, _| {
+            scene.despawn_recursive(id);
+        });
+    }
+
+    /** Queue inserting a component into an entity */
+    pub fn insert_component<T: 'static + Component>(&mut self, id: EntityId, component: T) {
+        self.add(move |scene, _| {
+            scene.insert_component(id, component);
+        });
+    }
+
+    /** Queue inserting the components of a bundle into an entity */
+    pub fn insert_bundle<B: 'static + Send + Bundle>(&mut self, id: EntityId, bundle: B) {
+        self.add(move |scene, _| {
+            scene.insert_bundle(id, bundle);
+        });
+    }
+
+    /** Queue removing the component of provided type from an entity */
+    pub fn remove_component<T: 'static + Component>(&mut self, id: EntityId) {
+        self.add(move |scene, _| {
+            scene.remove_component::<T>(id);
+        });
+    }
+
+    /** Queue setting the parent of an entity */
+    pub fn set_parent(&mut self, child: EntityId, parent: EntityId) {
+        self.add(move |scene, _| {
+            scene.set_parent(child, parent);
+        });
+    }
+
+    /** Queue detaching an entity from its parent */
+    pub fn remove_parent(&mut self, child: EntityId) {
+        self.add(move |scene, _| {
+            scene.remove_parent(child);
+        });
+    }
+
+    /** Queue inserting a resource in the world */
+    pub fn insert_resource<T: 'static + Send + Sync>(&mut self, resource: T) {
+        self.add(move |_, world| world.insert_resource(resource));
+    }
+
+    /** Queue removing the resource of provided type from the world */
+    pub fn remove_resource<T: 'static>(&mut self) {
+        self.add(|_, world| {
+            world.remove_resource::<T>();
+        });
+    }
+
+    /** Get the amount of queued commands */
+    pub fn len(&self) -> usize {
+        return self.queue.len();
+    }
+
+    /** Check if no commands are queued */
+    pub fn is_empty(&self) -> bool {
+        return self.queue.is_empty();
+    }
+
+    /** Move the commands queued in the other buffer to the end of the buffer */
+    pub(crate) fn append(&mut self, other: &mut Commands) {
+        self.queue.append(&mut other.queue);
+    }
+
+    /** Take the queued commands out of the buffer */
+    pub(crate) fn take(&mut self) -> Vec<Command> {
+        return std::mem::take(&mut self.queue);
+    }
+}
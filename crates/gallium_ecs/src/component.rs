@@ -1,30 +1,110 @@
-use std::cell::RefCell;
+use std::{
+    any::{Any, TypeId},
+    ops::Deref,
+    sync::RwLockReadGuard,
+};
 
-/** The trait to be inherited by any component */
+use crate::{Column, EntityMap, SparseSet, TypedColumn};
+
+/** The trait to be inherited by any component, components are Send + Sync so systems can run in parallel */
 #[typetag::serde(tag = "type")]
-pub trait Component {
+pub trait Component: ComponentBase + Send + Sync {
     /** Get component as any */
     fn as_any(&self) -> &dyn std::any::Any;
     /** Get component as mutable any */
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+    /** Get the storage the component is stored in */
+    fn storage_type(&self) -> StorageType {
+        return StorageType::Table;
+    }
+    /** Rewrite the entity ids stored in the component when the entities get new ids */
+    fn map_entities(&mut self, _map: &EntityMap) {}
 }
 
-/** Represents a component storage */
-#[typetag::serde(tag = "type")]
-pub trait ComponentStorage {
-    /** Get component as any */
-    fn as_any(&self) -> &dyn std::any::Any;
-    /** Get component as mutable any */
-    fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+/** The ways components can be stored in a scene */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageType {
+    /** Stored in the archetype tables, fast to iterate but slow to add and remove */
+    Table,
+    /** Stored in a sparse set, fast to add and remove but slower to iterate */
+    SparseSet,
 }
 
-#[typetag::serde]
-impl ComponentStorage for RefCell<Box<dyn Component>> {
-    fn as_any(&self) -> &dyn std::any::Any {
+/** Type-erased operations implemented automatically for every component */
+pub trait ComponentBase {
+    /** Convert the boxed component into a boxed any */
+    fn into_any(self: Box<Self>) -> Box<dyn std::any::Any>;
+    /** Get the type name of the component */
+    fn component_name(&self) -> &'static str;
+    /** Create an empty column that can store components of this type */
+    fn create_column(&self) -> Box<dyn Column>;
+    /** Create an empty sparse set that can store components of this type */
+    fn create_sparse_set(&self) -> Box<dyn ComponentStorage>;
+}
+
+impl<T: 'static + Component> ComponentBase for T {
+    fn into_any(self: Box<Self>) -> Box<dyn std::any::Any> {
         return self;
     }
 
-    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
-        return self;
+    fn component_name(&self) -> &'static str {
+        return std::any::type_name::<T>();
+    }
+
+    fn create_column(&self) -> Box<dyn Column> {
+        return Box::new(TypedColumn::<T>::new());
+    }
+
+    fn create_sparse_set(&self) -> Box<dyn ComponentStorage> {
+        return Box::new(SparseSet::<T>::new());
+    }
+}
+
+/** Represents a storage backend for components of a single type, indexed by entity */
+pub trait ComponentStorage: Send + Sync {
+    /** Get storage as any */
+    fn as_any(&self) -> &dyn Any;
+    /** Get storage as mutable any */
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /** Get the type-id of the stored components */
+    fn component_type(&self) -> TypeId;
+    /** Get the type name of the stored components */
+    fn component_name(&self) -> &'static str;
+    /** Check if the entity has a component in the storage */
+    fn contains(&self, entity: usize) -> bool;
+    /** Insert a boxed component for the entity at the tick, replacing the existing one */
+    fn insert_boxed(&mut self, entity: usize, component: Box<dyn Component>, change_tick: u64);
+    /** Remove the component of the entity */
+    fn remove_boxed(&mut self, entity: usize) -> Option<Box<dyn Component>>;
+    /** Borrow the component of the entity as a component */
+    fn try_get_dyn(&self, entity: usize) -> Option<ComponentRef<'_>>;
+}
+
+/** A shared borrow of a type-erased component */
+pub struct ComponentRef<'a>(Box<dyn Deref<Target = dyn Component> + 'a>);
+
+impl<'a> ComponentRef<'a> {
+    /** Borrow the locked component as a component */
+    pub(crate) fn new<T: 'static + Component>(guard: RwLockReadGuard<'a, T>) -> Self {
+        return Self(Box::new(ComponentGuard(guard)));
+    }
+}
+
+impl Deref for ComponentRef<'_> {
+    type Target = dyn Component;
+
+    fn deref(&self) -> &Self::Target {
+        return &**self.0;
+    }
+}
+
+/** Keeps a component of type T locked while it is borrowed as a component */
+struct ComponentGuard<'a, T>(RwLockReadGuard<'a, T>);
+
+impl<T: 'static + Component> Deref for ComponentGuard<'_, T> {
+    type Target = dyn Component;
+
+    fn deref(&self) -> &Self::Target {
+        return &*self.0;
     }
 }
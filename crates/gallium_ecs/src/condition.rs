@@ -0,0 +1,238 @@
+use serde::{de::DeserializeOwned, de::Error as DeError, Deserialize, Deserializer, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock, PoisonError},
+};
+
+use crate::{Events, Scene, World};
+
+/** Decides whether a system runs, evaluated by the scheduler right before the system would tick */
+#[typetag::serde(tag = "condition")]
+pub trait Condition: Send + Sync {
+    /** Check if the system should run, the running system is the system the condition belongs to */
+    fn evaluate(&mut self, scene: &Scene, world: &World) -> bool;
+}
+
+/** Composes conditions, implemented for every condition */
+pub trait ConditionExt: Condition + Sized + 'static {
+    /** Run only if both conditions hold, the other one isn't evaluated if this one doesn't */
+    fn and<C: 'static + Condition>(self, other: C) -> AllOf {
+        return AllOf(Box::new(self), Box::new(other));
+    }
+
+    /** Run if either condition holds, the other one isn't evaluated if this one does */
+    fn or<C: 'static + Condition>(self, other: C) -> AnyOf {
+        return AnyOf(Box::new(self), Box::new(other));
+    }
+
+    /** Run only if the condition doesn't hold */
+    fn not(self) -> NotCond {
+        return NotCond {
+            inner: Box::new(self),
+        };
+    }
+}
+
+impl<T: 'static + Condition> ConditionExt for T {}
+
+/** Holds if both conditions hold */
+#[derive(Serialize, Deserialize)]
+pub struct AllOf(pub Box<dyn Condition>, pub Box<dyn Condition>);
+
+#[typetag::serde]
+impl Condition for AllOf {
+    fn evaluate(&mut self, scene: &Scene, world: &World) -> bool {
+        return self.0.evaluate(scene, world) && self.1.evaluate(scene, world);
+    }
+}
+
+/** Holds if either condition holds */
+#[derive(Serialize, Deserialize)]
+pub struct AnyOf(pub Box<dyn Condition>, pub Box<dyn Condition>);
+
+#[typetag::serde]
+impl Condition for AnyOf {
+    fn evaluate(&mut self, scene: &Scene, world: &World) -> bool {
+        return self.0.evaluate(scene, world) || self.1.evaluate(scene, world);
+    }
+}
+
+/** Holds if the inner condition doesn't hold */
+#[derive(Serialize, Deserialize)]
+pub struct NotCond {
+    // The negated condition, a named field since a newtype would clash with the condition tag
+    pub inner: Box<dyn Condition>,
+}
+
+#[typetag::serde]
+impl Condition for NotCond {
+    fn evaluate(&mut self, scene: &Scene, world: &World) -> bool {
+        return !self.inner.evaluate(scene, world);
+    }
+}
+
+/** Holds every n-th time it is evaluated, starting with the first */
+#[derive(Serialize, Deserialize)]
+pub struct EveryNTicks {
+    // The amount of evaluations between runs
+    n: u32,
+    // The amount of evaluations since the last run
+    #[serde(default)]
+    elapsed: u32,
+}
+
+impl EveryNTicks {
+    /** Construct a condition holding every n-th evaluation, an n of 0 holds every evaluation */
+    pub fn new(n: u32) -> Self {
+        return Self { n, elapsed: 0 };
+    }
+}
+
+#[typetag::serde]
+impl Condition for EveryNTicks {
+    fn evaluate(&mut self, _scene: &Scene, _world: &World) -> bool {
+        let runs = self.elapsed == 0;
+        self.elapsed += 1;
+        if self.elapsed >= self.n {
+            self.elapsed = 0;
+        }
+        return runs;
+    }
+}
+
+/**
+ * Holds if the world has the resource of type T.
+ * Saved by the type name of the resource, loading requires a condition on the same type to have been constructed first
+ */
+pub fn resource_exists<T: 'static + Send + Sync>() -> WorldCheck {
+    return WorldCheck::register("resource_exists", std::any::type_name::<T>(), None, |_| {
+        return Ok(Box::new(|world: &World| world.has_resource::<T>()));
+    });
+}
+
+/**
+ * Holds if the world has the resource of type T and it equals the value.
+ * The value is saved along with the type name of the resource
+ */
+pub fn resource_equals<T: 'static + Send + Sync + PartialEq + Serialize + DeserializeOwned>(
+    value: T,
+) -> WorldCheck {
+    let saved = match ron::to_string(&value) {
+        Ok(saved) => saved,
+        Err(e) => panic!("Failed to serialize the resource value: {}", e),
+    };
+    return WorldCheck::register(
+        "resource_equals",
+        std::any::type_name::<T>(),
+        Some(saved),
+        |value| {
+            let value: T = ron::from_str(value.unwrap_or_default()).map_err(|e| e.to_string())?;
+            return Ok(Box::new(move |world: &World| {
+                world.has_resource::<T>() && *world.read_resource::<T>().unwrap() == value
+            }));
+        },
+    );
+}
+
+/** Holds if the system the condition belongs to has events of type T it hasn't read yet, false if the world has no such events */
+pub fn events_pending<T: 'static>() -> WorldCheck {
+    return WorldCheck::register("events_pending", std::any::type_name::<T>(), None, |_| {
+        return Ok(Box::new(|world: &World| {
+            world.has_resource::<Events<T>>() && world.has_unread_events::<T>()
+        }));
+    });
+}
+
+/** A built-in condition on the world, constructed by resource_exists, resource_equals and events_pending */
+pub struct WorldCheck {
+    // The kind of check and the type name it checks, with the saved value it compares to
+    saved: SavedWorldCheck,
+    // Checks the world
+    check: Check,
+}
+
+impl WorldCheck {
+    /** Construct the check and register how to construct it again when it is loaded */
+    fn register(
+        kind: &str,
+        type_name: &str,
+        value: Option<String>,
+        construct: fn(Option<&str>) -> Result<Check, String>,
+    ) -> Self {
+        let saved = SavedWorldCheck {
+            kind: String::from(kind),
+            type_name: String::from(type_name),
+            value,
+        };
+        let mut registry = registry().lock().unwrap_or_else(PoisonError::into_inner);
+        registry.insert(saved.key(), construct);
+        let check = match construct(saved.value.as_deref()) {
+            Ok(check) => check,
+            Err(e) => panic!("{}", e),
+        };
+        return Self { saved, check };
+    }
+}
+
+#[typetag::serde]
+impl Condition for WorldCheck {
+    fn evaluate(&mut self, _scene: &Scene, world: &World) -> bool {
+        return (self.check)(world);
+    }
+}
+
+impl Serialize for WorldCheck {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return self.saved.serialize(serializer);
+    }
+}
+
+impl<'de> Deserialize<'de> for WorldCheck {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = SavedWorldCheck::deserialize(deserializer)?;
+        let construct = match registry()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&saved.key())
+        {
+            Some(construct) => *construct,
+            None => {
+                return Err(D::Error::custom(format!(
+                    "Condition {} on {} hasn't been constructed yet",
+                    saved.kind, saved.type_name
+                )))
+            }
+        };
+        let check = construct(saved.value.as_deref()).map_err(D::Error::custom)?;
+        return Ok(Self { saved, check });
+    }
+}
+
+/** The saved form of a world check */
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "WorldCheck")]
+struct SavedWorldCheck {
+    kind: String,
+    type_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+}
+
+impl SavedWorldCheck {
+    /** Get the key the check is registered by */
+    fn key(&self) -> String {
+        return format!("{}<{}>", self.kind, self.type_name);
+    }
+}
+
+/** Checks the world for a world check */
+type Check = Box<dyn Fn(&World) -> bool + Send + Sync>;
+
+/** The constructors of the world checks by their kind and type name */
+type Registry = Mutex<HashMap<String, fn(Option<&str>) -> Result<Check, String>>>;
+
+/** Get the constructors of the world checks */
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    return REGISTRY.get_or_init(|| Mutex::new(HashMap::new()));
+}
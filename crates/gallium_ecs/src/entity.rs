@@ -8,15 +8,22 @@ use std::{
     cell::{RefCell, RefMut},
     fs::File,
     io::Write,
+    sync::RwLock,
 };
 
-use crate::{support::nameof, Component};
+use crate::{
+    support::nameof, Bundle, Component, ComponentAccess, ComponentTicks, EntityId, EntityLocation,
+    EntityMap, EntityMeta, Mut, QueryFilter, Scene,
+};
 
 /** Represents an entity with components */
 #[derive(Serialize, Deserialize)]
 pub struct Entity {
     // The entity id
-    pub(crate) id: usize,
+    pub(crate) id: EntityId,
+    // The id was loaded from a file, so other loaded entities may reference it
+    #[serde(skip, default = "loaded_id")]
+    pub(crate) has_saved_id: bool,
     // The entity is active
     pub is_active: bool,
 
@@ -69,7 +76,7 @@ impl Entity {
     }
 
     /** Get the entity id */
-    pub fn id(&self) -> usize {
+    pub fn id(&self) -> EntityId {
         return self.id;
     }
 
@@ -105,6 +112,189 @@ impl Entity {
         );
         return None;
     }
+
+    /** Insert a component, replacing the existing component of the same type */
+    pub fn insert_component<T: 'static + Component>(&mut self, component: T) {
+        self.insert_boxed(Box::new(component));
+    }
+
+    /** Insert the components of a bundle, replacing the existing components of the same types */
+    pub fn insert_bundle<B: Bundle>(&mut self, bundle: B) {
+        let mut components = vec![];
+        bundle.take_components(&mut components);
+        for component in components {
+            self.insert_boxed(component);
+        }
+    }
+
+    /** Insert a boxed component, replacing the existing component of the same type */
+    fn insert_boxed(&mut self, component: Box<dyn Component>) {
+        let name = String::from(component.component_name());
+        // Replace the component if the entity already has one
+        if let Some(index) = self.stored_components.iter().position(|n| n == &name) {
+            self.components[index] = RefCell::new(component);
+            return;
+        }
+        // Add the component
+        self.stored_components.push(name);
+        self.components.push(RefCell::new(component));
+    }
+
+    /** Remove the component of provided type, returning it */
+    pub fn remove_component<T: 'static + Component>(&mut self) -> Option<T> {
+        let name = std::any::type_name::<T>();
+        let index = self.stored_components.iter().position(|n| n == name)?;
+        // Remove the name and the component
+        self.stored_components.remove(index);
+        let component = self.components.remove(index).into_inner();
+        return match component.into_any().downcast::<T>() {
+            Ok(c) => Some(*c),
+            Err(_) => None,
+        };
+    }
+
+    /** Construct an entity from the components of a scene entity, keeping its id as saved id */
+    pub(crate) fn from_components(
+        id: EntityId,
+        is_active: bool,
+        components: Vec<Box<dyn Component>>,
+    ) -> Self {
+        return Self {
+            id,
+            has_saved_id: true,
+            is_active,
+            stored_components: components
+                .iter()
+                .map(|c| String::from(c.component_name()))
+                .collect(),
+            components: components.into_iter().map(RefCell::new).collect(),
+        };
+    }
+
+    /** Rewrite the entity ids stored in the components through the map */
+    pub fn map_entities(&mut self, map: &EntityMap) {
+        for component in self.components.iter_mut() {
+            component.get_mut().map_entities(map);
+        }
+    }
+
+    /** Take the components out of the entity */
+    pub(crate) fn take_components(self) -> Vec<Box<dyn Component>> {
+        return self
+            .components
+            .into_iter()
+            .map(|c| c.into_inner())
+            .collect();
+    }
+}
+
+/** Deserialized entities keep the id they were saved with */
+fn loaded_id() -> bool {
+    return true;
+}
+
+/** A reference to an entity stored in a scene */
+#[derive(Clone, Copy)]
+pub struct EntityRef<'a> {
+    // The scene storing the entity
+    scene: &'a Scene,
+    // The entity id
+    id: EntityId,
+}
+
+impl<'a> EntityRef<'a> {
+    /** Construct a reference to a living entity in the scene */
+    pub(crate) fn new(scene: &'a Scene, id: EntityId) -> Self {
+        return Self { scene, id };
+    }
+
+    /** Get the entity id */
+    pub fn id(&self) -> EntityId {
+        return self.id;
+    }
+
+    /** Check if the entity is active */
+    pub fn is_active(&self) -> bool {
+        return self.meta().is_active();
+    }
+
+    /** Set whether the entity is active */
+    pub fn set_active(&self, active: bool) {
+        self.meta().set_active(active);
+    }
+
+    /** Check if the entity has a component */
+    pub fn has_component<T: 'static + Component>(&self) -> bool {
+        // Check the archetype tables
+        let archetype = self.scene.archetypes.get(self.location().archetype);
+        if archetype.has_component::<T>() {
+            return true;
+        }
+        // Check the sparse sets
+        return match self.scene.sparse_sets.get::<T>() {
+            Some(set) => set.get(self.id.index()).is_some(),
+            None => false,
+        };
+    }
+
+    /** Check if the entity matches the query filter */
+    pub fn matches<F: QueryFilter<'a>>(&self) -> bool {
+        let location = self.location();
+        let archetype = self.scene.archetypes.get(location.archetype);
+        return match F::init(self.scene, archetype) {
+            Some(state) => F::matches(state, location.row, self.id),
+            None => false,
+        };
+    }
+
+    /** Get the component of provided type, marking it as changed when written to */
+    pub fn get_component<T: 'static + Component>(&self) -> Option<Mut<'a, T>> {
+        if let Some(component) = self.try_get_component::<T>() {
+            return Some(component);
+        }
+        println!(
+            "Component \'{}\' either doesn't exist in entity {} or is already borrowed",
+            std::any::type_name::<T>(),
+            self.id
+        );
+        return None;
+    }
+
+    /** Get the component of provided type without reporting when it is missing or borrowed */
+    pub(crate) fn try_get_component<T: 'static + Component>(&self) -> Option<Mut<'a, T>> {
+        let (lock, ticks) = self.component_cell::<T>()?;
+        // Borrow the component if it isn't already borrowed
+        return match lock.try_write() {
+            Ok(component) => Some(Mut::new(component, ticks, self.scene.change_tick)),
+            Err(_) => None,
+        };
+    }
+
+    /** Find the lock and change ticks of the component of provided type */
+    pub(crate) fn component_cell<T: 'static + Component>(
+        &self,
+    ) -> Option<(&'a RwLock<T>, &'a ComponentTicks)> {
+        let location = self.location();
+        let archetype = self.scene.archetypes.get(location.archetype);
+        // Find the component in the archetype tables or the sparse sets
+        let access = ComponentAccess::<T>::new(self.scene, archetype)?;
+        let cell = access.get(location.row, self.id)?;
+        return Some((cell, access.get_ticks(location.row, self.id).unwrap()));
+    }
+
+    /** Get the bookkeeping of the entity */
+    fn meta(&self) -> &'a EntityMeta {
+        return self
+            .scene
+            .entities
+            .get(self.id)
+            .expect("Entity reference outlived its entity");
+    }
+
+    /** Get the location of the entity's components */
+    fn location(&self) -> EntityLocation {
+        return self.meta().location.unwrap();
+    }
 }
 
 /** Builds an entity */
@@ -132,10 +322,23 @@ impl EntityBuilder {
         return self;
     }
 
+    /** Add the components of a bundle to the entity */
+    pub fn with_bundle<B: Bundle>(mut self, bundle: B) -> Self {
+        let mut components = vec![];
+        bundle.take_components(&mut components);
+        for component in components {
+            self.stored_components
+                .push(String::from(component.component_name()));
+            self.components.push(RefCell::new(component));
+        }
+        return self;
+    }
+
     /** Build the entity */
     pub fn build(self) -> Entity {
         return Entity {
-            id: 0,
+            id: EntityId::default(),
+            has_saved_id: false,
             is_active: true,
             components: self.components,
             stored_components: self.stored_components,
@@ -0,0 +1,206 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::EntityLocation;
+
+/** A handle to an entity in a scene, invalidated when the entity is despawned */
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntityId {
+    // The slot of the entity
+    index: usize,
+    // The amount of times the slot has been reused
+    generation: u32,
+}
+
+impl EntityId {
+    /** An id that never belongs to an entity, used for references to entities that don't exist */
+    pub const PLACEHOLDER: EntityId = EntityId {
+        index: usize::MAX,
+        generation: u32::MAX,
+    };
+
+    /** Construct an entity id from its parts */
+    pub fn new(index: usize, generation: u32) -> Self {
+        return Self { index, generation };
+    }
+
+    /** Get the slot of the entity */
+    pub fn index(&self) -> usize {
+        return self.index;
+    }
+
+    /** Get the generation of the slot */
+    pub fn generation(&self) -> u32 {
+        return self.generation;
+    }
+}
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}v{}", self.index, self.generation);
+    }
+}
+
+impl<'de> Deserialize<'de> for EntityId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        return match SerializedEntityId::deserialize(deserializer)? {
+            SerializedEntityId::Index(index) => Ok(Self::new(index, 0)),
+            SerializedEntityId::Handle { index, generation } => Ok(Self::new(index, generation)),
+        };
+    }
+}
+
+/** The serialized forms of an entity id, older files store a plain index */
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SerializedEntityId {
+    Index(usize),
+    Handle { index: usize, generation: u32 },
+}
+
+/** The bookkeeping a scene keeps for each entity slot */
+pub(crate) struct EntityMeta {
+    // The current generation of the slot
+    pub(crate) generation: u32,
+    // Where the entity's components are stored, none if the slot is free
+    pub(crate) location: Option<EntityLocation>,
+    // The entity is active, atomic so systems can toggle it in parallel
+    is_active: AtomicBool,
+}
+
+impl EntityMeta {
+    /** Check if the entity is active */
+    pub(crate) fn is_active(&self) -> bool {
+        return self.is_active.load(Ordering::Relaxed);
+    }
+
+    /** Set the entity active or inactive */
+    pub(crate) fn set_active(&self, is_active: bool) {
+        self.is_active.store(is_active, Ordering::Relaxed);
+    }
+}
+
+/** Allocates entity ids, recycling the slots of despawned entities */
+pub(crate) struct Entities {
+    // The entity slots
+    metas: Vec<EntityMeta>,
+    // The free slots
+    free: Vec<usize>,
+}
+
+impl Entities {
+    /** Construct an empty allocator */
+    pub(crate) fn new() -> Self {
+        return Self {
+            metas: vec![],
+            free: vec![],
+        };
+    }
+
+    /** Allocate an id, the entity becomes alive once its location is set */
+    pub(crate) fn alloc(&mut self, is_active: bool) -> EntityId {
+        // Reuse a free slot
+        if let Some(index) = self.free.pop() {
+            self.metas[index].set_active(is_active);
+            return EntityId::new(index, self.metas[index].generation);
+        }
+        // Create a new slot
+        self.metas.push(EntityMeta {
+            generation: 0,
+            location: None,
+            is_active: AtomicBool::new(is_active),
+        });
+        return EntityId::new(self.metas.len() - 1, 0);
+    }
+
+    /** Allocate a specific id, returns false if the slot is already taken */
+    pub(crate) fn alloc_at(&mut self, id: EntityId, is_active: bool) -> bool {
+        // Create free slots up to the id
+        while self.metas.len() <= id.index {
+            self.free.push(self.metas.len());
+            self.metas.push(EntityMeta {
+                generation: 0,
+                location: None,
+                is_active: AtomicBool::new(true),
+            });
+        }
+        // Take the slot
+        if !self.free.contains(&id.index) {
+            return false;
+        }
+        self.free.retain(|i| *i != id.index);
+        self.metas[id.index].generation = id.generation;
+        self.metas[id.index].set_active(is_active);
+        return true;
+    }
+
+    /** Restore a free slot with its saved generation, returns false if the slot is already taken */
+    pub(crate) fn free_at(&mut self, id: EntityId) -> bool {
+        if !self.alloc_at(id, true) {
+            return false;
+        }
+        self.free.push(id.index);
+        return true;
+    }
+
+    /** Give the free slots that weren't restored a generation above every saved generation */
+    pub(crate) fn invalidate_free(&mut self, restored: &[EntityId]) {
+        let next = match self.metas.iter().map(|meta| meta.generation).max() {
+            Some(generation) => generation.wrapping_add(1),
+            None => return,
+        };
+        for index in self.free.iter() {
+            if !restored.iter().any(|id| id.index == *index) {
+                self.metas[*index].generation = next;
+            }
+        }
+    }
+
+    /** Get the free slots with the generation the next entity in them gets */
+    pub(crate) fn free_slots(&self) -> impl Iterator<Item = EntityId> + '_ {
+        return self
+            .free
+            .iter()
+            .map(move |index| EntityId::new(*index, self.metas[*index].generation));
+    }
+
+    /** Free the slot of a living entity, returns false if the entity doesn't exist */
+    pub(crate) fn free(&mut self, id: EntityId) -> bool {
+        if self.get(id).is_none() {
+            return false;
+        }
+        // Invalidate existing ids to the slot
+        let meta = &mut self.metas[id.index];
+        meta.location = None;
+        meta.generation = meta.generation.wrapping_add(1);
+        self.free.push(id.index);
+        return true;
+    }
+
+    /** Set the location of an allocated entity */
+    pub(crate) fn set_location(&mut self, id: EntityId, location: EntityLocation) {
+        self.metas[id.index].location = Some(location);
+    }
+
+    /** Get the bookkeeping of a living entity */
+    pub(crate) fn get(&self, id: EntityId) -> Option<&EntityMeta> {
+        let meta = self.metas.get(id.index)?;
+        if meta.generation != id.generation || meta.location.is_none() {
+            return None;
+        }
+        return Some(meta);
+    }
+
+    /** Get the ids of the living entities */
+    pub(crate) fn iter(&self) -> impl Iterator<Item = EntityId> + '_ {
+        return self
+            .metas
+            .iter()
+            .enumerate()
+            .filter(|(_, meta)| meta.location.is_some())
+            .map(|(index, meta)| EntityId::new(index, meta.generation));
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::EntityId;
+
+/** Maps the ids entities had before being added to a scene to their ids in the scene */
+#[derive(Clone, Debug, Default)]
+pub struct EntityMap {
+    // The new id of each old id
+    map: HashMap<EntityId, EntityId>,
+}
+
+impl EntityMap {
+    /** Construct an empty map */
+    pub fn new() -> Self {
+        return Self {
+            map: HashMap::new(),
+        };
+    }
+
+    /** Map an old id to a new id */
+    pub fn insert(&mut self, old: EntityId, new: EntityId) {
+        self.map.insert(old, new);
+    }
+
+    /** Get the new id of an old id */
+    pub fn get(&self, old: EntityId) -> Option<EntityId> {
+        return self.map.get(&old).copied();
+    }
+
+    /** Get the new id of an old id, ids that aren't in the map become the placeholder id so they don't point at unrelated entities */
+    pub fn map(&self, id: EntityId) -> EntityId {
+        return self.get(id).unwrap_or(EntityId::PLACEHOLDER);
+    }
+
+    /** Get the old and new ids */
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, EntityId)> + '_ {
+        return self.map.iter().map(|(old, new)| (*old, *new));
+    }
+
+    /** Get the amount of mapped ids */
+    pub fn len(&self) -> usize {
+        return self.map.len();
+    }
+
+    /** Check if no ids are mapped */
+    pub fn is_empty(&self) -> bool {
+        return self.map.is_empty();
+    }
+}
+
+/** Data containing entity ids that have to be rewritten when the entities get new ids */
+pub trait MapEntities {
+    /** Rewrite the entity ids through the map */
+    fn map_entities(&mut self, map: &EntityMap);
+}
+
+impl MapEntities for EntityId {
+    fn map_entities(&mut self, map: &EntityMap) {
+        *self = map.map(*self);
+    }
+}
+
+impl<T: MapEntities> MapEntities for Option<T> {
+    fn map_entities(&mut self, map: &EntityMap) {
+        if let Some(value) = self {
+            value.map_entities(map);
+        }
+    }
+}
+
+impl<T: MapEntities> MapEntities for Vec<T> {
+    fn map_entities(&mut self, map: &EntityMap) {
+        for value in self.iter_mut() {
+            value.map_entities(map);
+        }
+    }
+}
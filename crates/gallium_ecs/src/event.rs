@@ -0,0 +1,127 @@
+use std::{collections::HashMap, sync::RwLockWriteGuard};
+
+use crate::World;
+
+/** A channel of events of type T, double buffered so events live until the second World::update_events */
+pub struct Events<T> {
+    // The events sent before the last update, with their ids
+    previous: Vec<(usize, T)>,
+    // The events sent since the last update, with their ids
+    current: Vec<(usize, T)>,
+    // The id of the next event
+    next_id: usize,
+    // The id of the next event each reader reads, readers are keyed by system
+    cursors: HashMap<Option<usize>, usize>,
+}
+
+impl<T: 'static> Default for Events<T> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl<T: 'static> Events<T> {
+    /** Construct an empty channel */
+    pub fn new() -> Self {
+        return Self {
+            previous: vec![],
+            current: vec![],
+            next_id: 0,
+            cursors: HashMap::new(),
+        };
+    }
+
+    /** Send an event */
+    pub fn send(&mut self, event: T) {
+        self.current.push((self.next_id, event));
+        self.next_id += 1;
+    }
+
+    /** Drop the events sent before the last update, the current events stay readable until the next one */
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    /** Get the amount of buffered events */
+    pub fn len(&self) -> usize {
+        return self.previous.len() + self.current.len();
+    }
+
+    /** Check if no events are buffered */
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    /** Drop all buffered events */
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    /** Check if the reader has events it hasn't read yet */
+    pub(crate) fn has_unread(&self, reader: Option<usize>) -> bool {
+        let cursor = self.cursors.get(&reader).copied().unwrap_or(0);
+        return self
+            .previous
+            .iter()
+            .chain(self.current.iter())
+            .any(|(id, _)| *id >= cursor);
+    }
+
+    /** Read the events the reader hasn't read yet, advancing its cursor */
+    pub(crate) fn read(&mut self, reader: Option<usize>) -> impl Iterator<Item = &T> {
+        let next_id = self.next_id;
+        let cursor = self.cursors.insert(reader, next_id).unwrap_or(0);
+        return self
+            .previous
+            .iter()
+            .chain(self.current.iter())
+            .filter(move |(id, _)| *id >= cursor)
+            .map(|(_, event)| event);
+    }
+}
+
+/** Sends events of type T */
+pub struct EventWriter<'a, T> {
+    // The channel of the events
+    events: RwLockWriteGuard<'a, Events<T>>,
+}
+
+impl<'a, T: 'static> EventWriter<'a, T> {
+    /** Construct a writer for the channel */
+    pub(crate) fn new(events: RwLockWriteGuard<'a, Events<T>>) -> Self {
+        return Self { events };
+    }
+
+    /** Send an event */
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+}
+
+/** Reads the events of type T the running system hasn't read yet */
+pub struct EventReader<'a, T> {
+    // The channel of the events
+    events: RwLockWriteGuard<'a, Events<T>>,
+    // The system reading the events, none outside of systems
+    reader: Option<usize>,
+}
+
+impl<'a, T: 'static> EventReader<'a, T> {
+    /** Construct a reader for the channel */
+    pub(crate) fn new(events: RwLockWriteGuard<'a, Events<T>>, reader: Option<usize>) -> Self {
+        return Self { events, reader };
+    }
+
+    /** Iterate over the unread events, marking them as read */
+    pub fn iter(&mut self) -> impl Iterator<Item = &T> {
+        return self.events.read(self.reader);
+    }
+}
+
+/** Update the channel of events of type T in the world */
+pub(crate) fn update_events<T: 'static>(world: &World) {
+    if let Some(mut events) = world.get_resource::<Events<T>>() {
+        events.update();
+    }
+}
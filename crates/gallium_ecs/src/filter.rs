@@ -0,0 +1,202 @@
+use std::{marker::PhantomData, sync::RwLockReadGuard};
+
+use crate::{Archetype, Component, ComponentAccess, EntityId, Mut, QueryData, Scene, SystemAccess};
+
+/** A condition entities in a scene have to match to be included by a query */
+pub trait QueryFilter<'a> {
+    /** The per-archetype state used to check the condition */
+    type State: Copy;
+
+    /** Prepare checking an archetype, returns none if no entity in it can match */
+    fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State>;
+    /** Check if the entity in the row matches */
+    fn matches(state: Self::State, row: usize, id: EntityId) -> bool;
+}
+
+/** Only include entities that have a component of type T */
+pub struct With<T>(PhantomData<T>);
+
+/** Only include entities that don't have a component of type T */
+pub struct Without<T>(PhantomData<T>);
+
+/** Only include entities that match any of the filters in the tuple */
+pub struct Or<T>(PhantomData<T>);
+
+/** Only include entities whose component of type T was added since the system last ran */
+pub struct Added<T>(PhantomData<T>);
+
+/** Only include entities whose component of type T was changed since the system last ran */
+pub struct Changed<T>(PhantomData<T>);
+
+impl<'a> QueryFilter<'a> for () {
+    type State = ();
+
+    fn init(_scene: &'a Scene, _archetype: &'a Archetype) -> Option<Self::State> {
+        return Some(());
+    }
+
+    fn matches(_state: Self::State, _row: usize, _id: EntityId) -> bool {
+        return true;
+    }
+}
+
+impl<'a, T: 'static + Component> QueryFilter<'a> for With<T> {
+    type State = ComponentAccess<'a, T>;
+
+    fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+        return ComponentAccess::new(scene, archetype);
+    }
+
+    fn matches(state: Self::State, row: usize, id: EntityId) -> bool {
+        return state.get(row, id).is_some();
+    }
+}
+
+impl<'a, T: 'static + Component> QueryFilter<'a> for Without<T> {
+    type State = Option<ComponentAccess<'a, T>>;
+
+    fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+        // Every entity in the archetype has the component if it is stored in the table
+        return match ComponentAccess::new(scene, archetype) {
+            Some(ComponentAccess::Table(_)) => None,
+            access => Some(access),
+        };
+    }
+
+    fn matches(state: Self::State, row: usize, id: EntityId) -> bool {
+        return match state {
+            Some(access) => access.get(row, id).is_none(),
+            None => true,
+        };
+    }
+}
+
+impl<'a, T: 'static + Component> QueryFilter<'a> for Added<T> {
+    type State = (ComponentAccess<'a, T>, u64);
+
+    fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+        let last_run = scene.last_change_tick();
+        return Some((ComponentAccess::new(scene, archetype)?, last_run));
+    }
+
+    fn matches(state: Self::State, row: usize, id: EntityId) -> bool {
+        let (access, last_run) = state;
+        return match access.get_ticks(row, id) {
+            Some(ticks) => ticks.is_added(last_run),
+            None => false,
+        };
+    }
+}
+
+impl<'a, T: 'static + Component> QueryFilter<'a> for Changed<T> {
+    type State = (ComponentAccess<'a, T>, u64);
+
+    fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+        let last_run = scene.last_change_tick();
+        return Some((ComponentAccess::new(scene, archetype)?, last_run));
+    }
+
+    fn matches(state: Self::State, row: usize, id: EntityId) -> bool {
+        let (access, last_run) = state;
+        return match access.get_ticks(row, id) {
+            Some(ticks) => ticks.is_changed(last_run),
+            None => false,
+        };
+    }
+}
+
+/** Implement query filters for a tuple of filters, and for or-ing them */
+macro_rules! impl_query_filter_tuple {
+    ($($name:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<'a, $($name: QueryFilter<'a>),*> QueryFilter<'a> for ($($name,)*) {
+            type State = ($($name::State,)*);
+
+            fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+                return Some(($($name::init(scene, archetype)?,)*));
+            }
+
+            fn matches(state: Self::State, row: usize, id: EntityId) -> bool {
+                let ($($name,)*) = state;
+                return true $(&& $name::matches($name, row, id))*;
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<'a, $($name: QueryFilter<'a>),*> QueryFilter<'a> for Or<($($name,)*)> {
+            type State = ($(Option<$name::State>,)*);
+
+            fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+                let state = ($($name::init(scene, archetype),)*);
+                let ($($name,)*) = state;
+                // Skip the archetype if none of the filters can match
+                if true $(&& $name.is_none())* {
+                    return None;
+                }
+                return Some(state);
+            }
+
+            fn matches(state: Self::State, row: usize, id: EntityId) -> bool {
+                let ($($name,)*) = state;
+                return false $(|| $name.map_or(false, |s| $name::matches(s, row, id)))*;
+            }
+        }
+    };
+}
+
+impl_query_filter_tuple!(A);
+impl_query_filter_tuple!(A, B);
+impl_query_filter_tuple!(A, B, C);
+impl_query_filter_tuple!(A, B, C, D);
+impl_query_filter_tuple!(A, B, C, D, E);
+impl_query_filter_tuple!(A, B, C, D, E, F);
+impl_query_filter_tuple!(A, B, C, D, E, F, G);
+impl_query_filter_tuple!(A, B, C, D, E, F, G, H);
+
+impl<'a, T: 'static + Component> QueryData<'a> for Option<&'a T> {
+    type Item = Option<RwLockReadGuard<'a, T>>;
+    type State = Option<ComponentAccess<'a, T>>;
+
+    fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+        return Some(ComponentAccess::new(scene, archetype));
+    }
+
+    fn matches(_state: Self::State, _row: usize, _id: EntityId) -> bool {
+        return true;
+    }
+
+    fn fetch(state: Self::State, row: usize, id: EntityId) -> Self::Item {
+        return match state?.get(row, id) {
+            Some(_) => Some(<&T as QueryData>::fetch(state?, row, id)),
+            None => None,
+        };
+    }
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().read::<T>());
+    }
+}
+
+impl<'a, T: 'static + Component> QueryData<'a> for Option<&'a mut T> {
+    type Item = Option<Mut<'a, T>>;
+    type State = (Option<ComponentAccess<'a, T>>, u64);
+
+    fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+        return Some((ComponentAccess::new(scene, archetype), scene.change_tick));
+    }
+
+    fn matches(_state: Self::State, _row: usize, _id: EntityId) -> bool {
+        return true;
+    }
+
+    fn fetch(state: Self::State, row: usize, id: EntityId) -> Self::Item {
+        let access = state.0?;
+        return access
+            .get(row, id)
+            .map(|_| <&mut T as QueryData>::fetch((access, state.1), row, id));
+    }
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().write::<T>());
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+/** Runs a tag a whole number of fixed steps per update, carrying the leftover time to the next update */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixedTimestep {
+    // The time each step simulates
+    step: Duration,
+    // The most steps run in one update
+    max_steps: u32,
+    // The elapsed time not simulated yet
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    /** Construct a timestep running at most 5 steps per update, panics if the step is zero */
+    pub fn new(step: Duration) -> Self {
+        if step.is_zero() {
+            panic!("Fixed timestep can't have a step of zero");
+        }
+        return Self {
+            step,
+            max_steps: 5,
+            accumulator: Duration::ZERO,
+        };
+    }
+
+    /** Construct a timestep running the amount of steps per second, panics if the amount isn't positive */
+    pub fn from_hz(hz: f64) -> Self {
+        if hz.is_nan() || hz <= 0.0 {
+            panic!(
+                "Fixed timestep needs a positive amount of steps per second, got: {}",
+                hz
+            );
+        }
+        return Self::new(Duration::from_secs_f64(1.0 / hz));
+    }
+
+    /** Set the most steps run in one update, the time beyond them is dropped instead of piling up */
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        return self;
+    }
+
+    /** Get the time each step simulates */
+    pub fn step(&self) -> Duration {
+        return self.step;
+    }
+
+    /** Get the most steps run in one update */
+    pub fn max_steps(&self) -> u32 {
+        return self.max_steps;
+    }
+
+    /** Get the elapsed time not simulated yet */
+    pub fn accumulator(&self) -> Duration {
+        return self.accumulator;
+    }
+
+    /** Get how far the leftover time is into the next step, from 0 to 1, used to interpolate between steps */
+    pub fn alpha(&self) -> f64 {
+        return self.accumulator.as_secs_f64() / self.step.as_secs_f64();
+    }
+
+    /** Add the elapsed time, returning the amount of steps to run */
+    pub(crate) fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+        let steps = self.accumulator.as_nanos() / self.step.as_nanos();
+        // Drop the time beyond the max steps, so slow steps don't cause ever more steps
+        if steps > self.max_steps as u128 {
+            self.accumulator =
+                Duration::from_nanos((self.accumulator.as_nanos() % self.step.as_nanos()) as u64);
+            return self.max_steps;
+        }
+        self.accumulator -= self.step * steps as u32;
+        return steps as u32;
+    }
+}
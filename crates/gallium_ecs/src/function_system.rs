@@ -0,0 +1,262 @@
+use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    sync::{Mutex, OnceLock, PoisonError},
+};
+
+use crate::{Scene, System, SystemAccess, SystemParam, SystemParamItem, World};
+
+/** Converts a value into a system, implemented for systems and for functions taking system params */
+pub trait IntoSystem<Marker> {
+    /** Convert the value into a boxed system */
+    fn into_system(self) -> Box<dyn System>;
+}
+
+impl<T: 'static + System> IntoSystem<()> for T {
+    fn into_system(self) -> Box<dyn System> {
+        return Box::new(self);
+    }
+}
+
+/** Marks the conversion of functions into systems */
+pub struct FunctionMarker;
+
+impl<Marker, F: SystemParamFunction<Marker> + Clone> IntoSystem<(FunctionMarker, Marker)> for F {
+    fn into_system(self) -> Box<dyn System> {
+        return Box::new(FunctionSystem::new(self));
+    }
+}
+
+/** A function whose parameters are all system params, implemented for functions of up to 8 params */
+pub trait SystemParamFunction<Marker>: Send + Sync + 'static {
+    /** Add what the parameters access, one entry per borrow */
+    fn access(accesses: &mut Vec<SystemAccess>);
+    /** Fetch the parameters and call the function */
+    fn run(&mut self, scene: &Scene, world: &World);
+}
+
+/** Implement system param functions for functions taking the params */
+macro_rules! impl_system_param_function {
+    ($($param:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<Func, $($param: SystemParam),*> SystemParamFunction<fn($($param,)*)> for Func
+        where
+            Func: Send + Sync + 'static,
+            for<'a> &'a mut Func: FnMut($($param),*) + FnMut($(SystemParamItem<$param>),*),
+        {
+            #[allow(unused_variables)]
+            fn access(accesses: &mut Vec<SystemAccess>) {
+                $($param::access(accesses);)*
+            }
+
+            #[allow(unused_variables)]
+            fn run(&mut self, scene: &Scene, world: &World) {
+                // Calling through a generic function lets the compiler pick the FnMut taking the items
+                #[allow(clippy::too_many_arguments)]
+                fn call<$($param),*>(mut f: impl FnMut($($param),*), $($param: $param),*) {
+                    f($($param),*);
+                }
+                $(let $param = $param::fetch(scene, world);)*
+                call(self, $($param),*);
+            }
+        }
+    };
+}
+
+impl_system_param_function!();
+impl_system_param_function!(A);
+impl_system_param_function!(A, B);
+impl_system_param_function!(A, B, C);
+impl_system_param_function!(A, B, C, D);
+impl_system_param_function!(A, B, C, D, E);
+impl_system_param_function!(A, B, C, D, E, F);
+impl_system_param_function!(A, B, C, D, E, F, G);
+impl_system_param_function!(A, B, C, D, E, F, G, H);
+
+/**
+ * A system running a function, its parameters are fetched from the scene and world.
+ * Saved by the name of the function, loading requires the function to have been made into a system first.
+ * Closures have no name of their own, they are made into systems with FunctionSystem::named
+ */
+pub struct FunctionSystem {
+    // The type name of the function
+    name: String,
+    // What the parameters access
+    access: SystemAccess,
+    // Fetches the parameters and calls the function
+    run: RunFunction,
+}
+
+impl FunctionSystem {
+    /**
+     * Construct a system from a function, named by the path of the function.
+     * Panics if the function is a closure, or if its parameters borrow the same data conflictingly
+     */
+    pub fn new<Marker, F: SystemParamFunction<Marker> + Clone>(function: F) -> Self {
+        return Self::named(function_name::<F>(), function);
+    }
+
+    /**
+     * Construct a system from a function or closure with a name to save it by, its label is the last segment of the name.
+     * Panics if the name is used by another function, or if its parameters borrow the same data conflictingly
+     */
+    pub fn named<Marker, F: SystemParamFunction<Marker> + Clone>(name: &str, function: F) -> Self {
+        let system = Self::from_function(name, function.clone());
+        // Register the function so saved scenes can be loaded
+        Self::register_named(name, function);
+        return system;
+    }
+
+    /** Register a function so scenes saved with it can be loaded before it is made into a system */
+    pub fn register<Marker, F: SystemParamFunction<Marker> + Clone>(function: F) {
+        Self::register_named(function_name::<F>(), function);
+    }
+
+    /** Register a function or closure by name, panics if the name is used by another function */
+    pub fn register_named<Marker, F: SystemParamFunction<Marker> + Clone>(name: &str, function: F) {
+        let mut registry = registry().lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some((function_type, _)) = registry.get(name) {
+            if *function_type != TypeId::of::<F>() {
+                panic!(
+                    "Function system name {} is already used by another function",
+                    name
+                );
+            }
+        }
+        let name = String::from(name);
+        let construct_name = name.clone();
+        let construct: ConstructSystem =
+            Box::new(move || Self::from_function(&construct_name, function.clone()));
+        registry.insert(name, (TypeId::of::<F>(), construct));
+    }
+
+    /** Get the type name of the function */
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /** Construct a system from a function without registering it */
+    fn from_function<Marker, F: SystemParamFunction<Marker>>(name: &str, mut function: F) -> Self {
+        // Every borrow has to be compatible with the other borrows
+        let mut accesses = vec![];
+        F::access(&mut accesses);
+        for (i, access) in accesses.iter().enumerate() {
+            for other in accesses[i + 1..].iter() {
+                if let Some(conflict) = access.conflict(other) {
+                    panic!(
+                        "Function system {} borrows {} mutably while also borrowing it elsewhere",
+                        name, conflict
+                    );
+                }
+            }
+        }
+        let mut access = SystemAccess::new();
+        for a in accesses {
+            access.extend(a);
+        }
+        return Self {
+            name: String::from(name),
+            access,
+            run: Box::new(move |scene, world| function.run(scene, world)),
+        };
+    }
+}
+
+#[typetag::serde]
+impl System for FunctionSystem {
+    fn tick(&mut self, scene: &mut Scene, world: &mut World) {
+        (self.run)(scene, world);
+    }
+
+    fn access(&self) -> Option<SystemAccess> {
+        return Some(self.access.clone());
+    }
+
+    fn tick_parallel(&mut self, scene: &Scene, world: &World) {
+        (self.run)(scene, world);
+    }
+
+    /** Function systems are referred to by the name of the function without its path and generic arguments */
+    fn default_label(&self) -> &str {
+        return strip_generics(&self.name).rsplit("::").next().unwrap();
+    }
+}
+
+impl Serialize for FunctionSystem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return SavedFunctionSystem {
+            name: self.name.clone(),
+        }
+        .serialize(serializer);
+    }
+}
+
+impl<'de> Deserialize<'de> for FunctionSystem {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = SavedFunctionSystem::deserialize(deserializer)?;
+        let registry = registry().lock().unwrap_or_else(PoisonError::into_inner);
+        return match registry.get(&saved.name) {
+            Some((_, construct)) => Ok(construct()),
+            None => Err(D::Error::custom(format!(
+                "Function system {} hasn't been made into a system yet",
+                saved.name
+            ))),
+        };
+    }
+}
+
+/** The saved form of a function system */
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "FunctionSystem")]
+struct SavedFunctionSystem {
+    name: String,
+}
+
+/** Fetches the parameters of a function system and calls its function */
+type RunFunction = Box<dyn FnMut(&Scene, &World) + Send + Sync>;
+
+/** Constructs the system of a registered function */
+type ConstructSystem = Box<dyn Fn() -> FunctionSystem + Send + Sync>;
+
+/** The functions made into systems by their name, along with the type of the function */
+type Registry = Mutex<HashMap<String, (TypeId, ConstructSystem)>>;
+
+/** Get the functions made into systems by their name */
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    return REGISTRY.get_or_init(|| Mutex::new(HashMap::new()));
+}
+
+/** Get the path of a function, panics for closures since they share the name of their parent function */
+fn function_name<F>() -> &'static str {
+    let name = std::any::type_name::<F>();
+    if name.contains("{{closure}}") {
+        panic!(
+            "Closure {} can't be saved by its name, make it into a system with FunctionSystem::named",
+            name
+        );
+    }
+    return name;
+}
+
+/** Strip the generic arguments from the end of a type name */
+fn strip_generics(name: &str) -> &str {
+    if !name.ends_with('>') {
+        return name;
+    }
+    let mut depth = 0;
+    for (i, c) in name.char_indices().rev() {
+        match c {
+            '>' => depth += 1,
+            '<' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &name[..i];
+                }
+            }
+            _ => {}
+        }
+    }
+    return name;
+}
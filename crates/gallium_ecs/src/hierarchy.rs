@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Component, EntityId, EntityMap, MapEntities, Scene};
+
+/** The parent of an entity, maintained by the scene */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Parent(EntityId);
+
+impl Parent {
+    /** Get the id of the parent */
+    pub fn get(&self) -> EntityId {
+        return self.0;
+    }
+}
+
+#[typetag::serde]
+impl Component for Parent {
+    fn as_any(&self) -> &dyn std::any::Any {
+        return self;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        return self;
+    }
+
+    fn map_entities(&mut self, map: &EntityMap) {
+        self.0.map_entities(map);
+    }
+}
+
+/** The children of an entity in the order they were added, maintained by the scene */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Children(Vec<EntityId>);
+
+impl Children {
+    /** Get the ids of the children */
+    pub fn get(&self) -> &[EntityId] {
+        return &self.0;
+    }
+}
+
+#[typetag::serde]
+impl Component for Children {
+    fn as_any(&self) -> &dyn std::any::Any {
+        return self;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        return self;
+    }
+
+    fn map_entities(&mut self, map: &EntityMap) {
+        self.0.map_entities(map);
+    }
+}
+
+impl Scene {
+    /**
+     * Set the parent of an entity, replacing its current parent.
+     * Returns false if either entity doesn't exist or the parent is the entity or one of its descendants
+     */
+    pub fn set_parent(&mut self, child: EntityId, parent: EntityId) -> bool {
+        if self.entities.get(child).is_none() || self.entities.get(parent).is_none() {
+            return false;
+        }
+        // The hierarchy can't contain cycles
+        if child == parent || self.descendants(child).contains(&parent) {
+            println!(
+                "Entity {} can't be parented to itself or its descendant {}",
+                child, parent
+            );
+            return false;
+        }
+        // Detach the entity from its current parent
+        self.remove_parent(child);
+        self.insert_component(child, Parent(parent));
+        // Add the entity to the children of the parent
+        let entity = self.get_entity(parent).unwrap();
+        let has_children = match entity.try_get_component::<Children>() {
+            Some(mut children) => {
+                children.0.push(child);
+                true
+            }
+            None => false,
+        };
+        if !has_children {
+            self.insert_component(parent, Children(vec![child]));
+        }
+        return true;
+    }
+
+    /** Detach an entity from its parent, returning the id of the parent */
+    pub fn remove_parent(&mut self, child: EntityId) -> Option<EntityId> {
+        if !self.get_entity(child)?.has_component::<Parent>() {
+            return None;
+        }
+        let parent = self.remove_component::<Parent>(child)?.0;
+        // Remove the entity from the children of the parent
+        let entity = self.get_entity(parent)?;
+        let is_empty = match entity.try_get_component::<Children>() {
+            Some(mut children) => {
+                children.0.retain(|c| *c != child);
+                children.0.is_empty()
+            }
+            None => false,
+        };
+        if is_empty {
+            self.remove_component::<Children>(parent);
+        }
+        return Some(parent);
+    }
+
+    /** Get the parent of an entity */
+    pub fn get_parent(&self, id: EntityId) -> Option<EntityId> {
+        let (parent, _) = self.get_entity(id)?.component_cell::<Parent>()?;
+        return match parent.try_read() {
+            Ok(parent) => Some(parent.0),
+            Err(_) => panic!("Parent of entity {} is already mutably borrowed", id),
+        };
+    }
+
+    /** Get the children of an entity */
+    pub fn get_children(&self, id: EntityId) -> Vec<EntityId> {
+        let entity = match self.get_entity(id) {
+            Some(e) => e,
+            None => return vec![],
+        };
+        return match entity.component_cell::<Children>() {
+            Some((children, _)) => match children.try_read() {
+                Ok(children) => children.0.clone(),
+                Err(_) => panic!("Children of entity {} are already mutably borrowed", id),
+            },
+            None => vec![],
+        };
+    }
+
+    /** Get the descendants of an entity, every entity comes before its own descendants */
+    pub fn descendants(&self, id: EntityId) -> Vec<EntityId> {
+        let mut descendants = vec![];
+        // Walk the hierarchy depth first
+        let mut stack = self.get_children(id);
+        stack.reverse();
+        while let Some(child) = stack.pop() {
+            descendants.push(child);
+            let mut children = self.get_children(child);
+            children.reverse();
+            stack.append(&mut children);
+        }
+        return descendants;
+    }
+
+    /** Despawn an entity along with its descendants, returns false if the entity doesn't exist */
+    pub fn despawn_recursive(&mut self, id: EntityId) -> bool {
+        if self.entities.get(id).is_none() {
+            return false;
+        }
+        // Despawn the descendants before their parents
+        for descendant in self.descendants(id).into_iter().rev() {
+            self.despawn(descendant);
+        }
+        return self.despawn(id);
+    }
+}
@@ -1,15 +1,55 @@
 //! A simple ecs system designed for serialization
+pub mod archetype;
+pub mod bundle;
+pub mod change_detection;
+pub mod commands;
 pub mod component;
+pub mod condition;
 pub mod entity;
+pub mod entity_id;
+pub mod entity_map;
+pub mod event;
+pub mod filter;
+pub mod fixed_timestep;
+pub mod function_system;
+pub mod hierarchy;
+pub mod query;
+pub mod removal_detection;
 pub mod scene;
+pub mod scene_stack;
+pub mod schedule;
+pub mod sparse_set;
 pub mod support;
 pub mod system;
+pub mod system_access;
+pub mod system_order;
+pub mod system_param;
 pub mod world;
 
+pub use archetype::*;
+pub use bundle::*;
+pub use change_detection::*;
+pub use commands::*;
 pub use component::*;
+pub use condition::*;
 pub use entity::*;
+pub use entity_id::*;
+pub use entity_map::*;
+pub use event::*;
+pub use filter::*;
+pub use fixed_timestep::*;
+pub use function_system::*;
+pub use hierarchy::*;
+pub use query::*;
+pub use removal_detection::*;
 pub use scene::*;
+pub use scene_stack::*;
+pub use schedule::*;
+pub use sparse_set::*;
 pub use system::*;
+pub use system_access::*;
+pub use system_order::*;
+pub use system_param::*;
 pub use world::*;
 
 // Publicly use serialization crates
@@ -0,0 +1,263 @@
+use std::{
+    marker::PhantomData,
+    sync::{RwLock, RwLockReadGuard},
+};
+
+use crate::{
+    Archetype, Component, ComponentTicks, EntityId, Mut, QueryFilter, Scene, SparseSet,
+    SystemAccess, TypedColumn,
+};
+
+/** Data that can be fetched from the entities in a scene by a query */
+pub trait QueryData<'a> {
+    /** The fetched data of a single entity */
+    type Item;
+    /** The per-archetype state used to fetch the data */
+    type State: Copy;
+
+    /** Prepare fetching from an archetype, returns none if no entity in it can match */
+    fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State>;
+    /** Check if the entity in the row matches */
+    fn matches(state: Self::State, row: usize, id: EntityId) -> bool;
+    /** Fetch the data of the entity in the row */
+    fn fetch(state: Self::State, row: usize, id: EntityId) -> Self::Item;
+    /** Add what the query data borrows, one entry per component */
+    fn access(accesses: &mut Vec<SystemAccess>);
+}
+
+/** Check that the query data doesn't borrow a component mutably while also borrowing it elsewhere */
+pub fn check_query_access<'a, Q: QueryData<'a>>() -> Result<(), String> {
+    let mut accesses = vec![];
+    Q::access(&mut accesses);
+    for (i, access) in accesses.iter().enumerate() {
+        for other in accesses[i + 1..].iter() {
+            if let Some(conflict) = access.conflict(other) {
+                return Err(format!(
+                    "Query {} borrows {} mutably while also borrowing it elsewhere",
+                    std::any::type_name::<Q>(),
+                    conflict
+                ));
+            }
+        }
+    }
+    return Ok(());
+}
+
+/** Where the components of type T are stored for an archetype */
+pub enum ComponentAccess<'a, T> {
+    /** Stored in a column of the archetype */
+    Table(&'a TypedColumn<T>),
+    /** Stored in a sparse set */
+    SparseSet(&'a SparseSet<T>),
+}
+
+impl<T> Clone for ComponentAccess<'_, T> {
+    fn clone(&self) -> Self {
+        return *self;
+    }
+}
+
+impl<T> Copy for ComponentAccess<'_, T> {}
+
+impl<'a, T: 'static + Component> ComponentAccess<'a, T> {
+    /** Find the storage of the components for an archetype */
+    pub fn new(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self> {
+        if let Some(column) = archetype.get_column::<T>() {
+            return Some(ComponentAccess::Table(column));
+        }
+        return match scene.sparse_sets.get::<T>() {
+            Some(set) => Some(ComponentAccess::SparseSet(set)),
+            None => None,
+        };
+    }
+
+    /** Get the component cell of an entity */
+    pub fn get(self, row: usize, id: EntityId) -> Option<&'a RwLock<T>> {
+        return match self {
+            ComponentAccess::Table(column) => column.get(row),
+            ComponentAccess::SparseSet(set) => set.get(id.index()),
+        };
+    }
+
+    /** Get the change ticks of the component of an entity */
+    pub fn get_ticks(self, row: usize, id: EntityId) -> Option<&'a ComponentTicks> {
+        return match self {
+            ComponentAccess::Table(column) => column.get_ticks(row),
+            ComponentAccess::SparseSet(set) => set.get_ticks(id.index()),
+        };
+    }
+}
+
+impl<'a, T: 'static + Component> QueryData<'a> for &'a T {
+    type Item = RwLockReadGuard<'a, T>;
+    type State = ComponentAccess<'a, T>;
+
+    fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+        return ComponentAccess::new(scene, archetype);
+    }
+
+    fn matches(state: Self::State, row: usize, id: EntityId) -> bool {
+        return state.get(row, id).is_some();
+    }
+
+    fn fetch(state: Self::State, row: usize, id: EntityId) -> Self::Item {
+        return match state.get(row, id).unwrap().try_read() {
+            Ok(c) => c,
+            Err(_) => panic!(
+                "Component '{}' of entity {} is already mutably borrowed",
+                std::any::type_name::<T>(),
+                id
+            ),
+        };
+    }
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().read::<T>());
+    }
+}
+
+impl<'a, T: 'static + Component> QueryData<'a> for &'a mut T {
+    type Item = Mut<'a, T>;
+    type State = (ComponentAccess<'a, T>, u64);
+
+    fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+        return Some((ComponentAccess::new(scene, archetype)?, scene.change_tick));
+    }
+
+    fn matches(state: Self::State, row: usize, id: EntityId) -> bool {
+        return state.0.get(row, id).is_some();
+    }
+
+    fn fetch(state: Self::State, row: usize, id: EntityId) -> Self::Item {
+        let (access, change_tick) = state;
+        let ticks = access.get_ticks(row, id).unwrap();
+        return match access.get(row, id).unwrap().try_write() {
+            Ok(c) => Mut::new(c, ticks, change_tick),
+            Err(_) => panic!(
+                "Component '{}' of entity {} is already borrowed",
+                std::any::type_name::<T>(),
+                id
+            ),
+        };
+    }
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().write::<T>());
+    }
+}
+
+/** Fetches nothing, for queries that only need the ids of the entities matching a filter */
+impl<'a> QueryData<'a> for () {
+    type Item = ();
+    type State = ();
+
+    fn init(_scene: &'a Scene, _archetype: &'a Archetype) -> Option<Self::State> {
+        return Some(());
+    }
+
+    fn matches(_state: Self::State, _row: usize, _id: EntityId) -> bool {
+        return true;
+    }
+
+    fn fetch(_state: Self::State, _row: usize, _id: EntityId) -> Self::Item {}
+
+    fn access(_accesses: &mut Vec<SystemAccess>) {}
+}
+
+/** Implement query data for a tuple of query data */
+macro_rules! impl_query_data_tuple {
+    ($($name:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<'a, $($name: QueryData<'a>),*> QueryData<'a> for ($($name,)*) {
+            type Item = ($($name::Item,)*);
+            type State = ($($name::State,)*);
+
+            fn init(scene: &'a Scene, archetype: &'a Archetype) -> Option<Self::State> {
+                return Some(($($name::init(scene, archetype)?,)*));
+            }
+
+            fn matches(state: Self::State, row: usize, id: EntityId) -> bool {
+                let ($($name,)*) = state;
+                return true $(&& $name::matches($name, row, id))*;
+            }
+
+            fn fetch(state: Self::State, row: usize, id: EntityId) -> Self::Item {
+                let ($($name,)*) = state;
+                return ($($name::fetch($name, row, id),)*);
+            }
+
+            fn access(accesses: &mut Vec<SystemAccess>) {
+                $($name::access(accesses);)*
+            }
+        }
+    };
+}
+
+impl_query_data_tuple!(A);
+impl_query_data_tuple!(A, B);
+impl_query_data_tuple!(A, B, C);
+impl_query_data_tuple!(A, B, C, D);
+impl_query_data_tuple!(A, B, C, D, E);
+impl_query_data_tuple!(A, B, C, D, E, F);
+impl_query_data_tuple!(A, B, C, D, E, F, G);
+impl_query_data_tuple!(A, B, C, D, E, F, G, H);
+
+/** Iterates over the active entities in a scene matching the query data and filter */
+pub struct QueryIter<'a, Q: QueryData<'a>, F: QueryFilter<'a> = ()> {
+    // The scene being queried
+    scene: &'a Scene,
+    // The archetypes left to visit
+    archetypes: std::slice::Iter<'a, Archetype>,
+    // The archetype being visited and its fetch and filter state
+    current: Option<(&'a Archetype, Q::State, F::State)>,
+    // The next row in the current archetype
+    row: usize,
+    // The filter is only used through its type
+    filter: PhantomData<F>,
+}
+
+impl<'a, Q: QueryData<'a>, F: QueryFilter<'a>> QueryIter<'a, Q, F> {
+    /** Construct an iterator over the scene */
+    pub(crate) fn new(scene: &'a Scene) -> Self {
+        return Self {
+            scene,
+            archetypes: scene.archetypes.iter(),
+            current: None,
+            row: 0,
+            filter: PhantomData,
+        };
+    }
+}
+
+impl<'a, Q: QueryData<'a>, F: QueryFilter<'a>> Iterator for QueryIter<'a, Q, F> {
+    type Item = (EntityId, Q::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Fetch the next matching entity in the current archetype
+            if let Some((archetype, state, filter)) = self.current {
+                while self.row < archetype.len() {
+                    let row = self.row;
+                    self.row += 1;
+                    let id = archetype.entities()[row];
+                    if !self.scene.entities.get(id).unwrap().is_active() {
+                        continue;
+                    }
+                    if Q::matches(state, row, id) && F::matches(filter, row, id) {
+                        return Some((id, Q::fetch(state, row, id)));
+                    }
+                }
+            }
+            // Move on to the next archetype
+            let archetype = self.archetypes.next()?;
+            self.current = match (
+                Q::init(self.scene, archetype),
+                F::init(self.scene, archetype),
+            ) {
+                (Some(state), Some(filter)) => Some((archetype, state, filter)),
+                _ => None,
+            };
+            self.row = 0;
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    any::TypeId,
+    collections::{HashMap, VecDeque},
+    marker::PhantomData,
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{Component, EntityId};
+
+/** Iterates over the entities that lost a component of type T since the running system last ran */
+pub struct RemovedComponents<'a, T> {
+    // The recorded removals of the component type
+    removed: std::slice::Iter<'a, (EntityId, u64)>,
+    // The change tick the running system last ran at
+    last_run: u64,
+    // The component type is only used to look up the removals
+    component: PhantomData<T>,
+}
+
+impl<'a, T: 'static + Component> RemovedComponents<'a, T> {
+    /** Construct a reader over the removals of a component type */
+    pub(crate) fn new(removals: &'a Removals, last_run: u64) -> Self {
+        let removed = match removals.removed.get(&TypeId::of::<T>()) {
+            Some(removed) => removed.iter(),
+            None => [].iter(),
+        };
+        return Self {
+            removed,
+            last_run,
+            component: PhantomData,
+        };
+    }
+}
+
+impl<T> Iterator for RemovedComponents<'_, T> {
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Skip removals the running system has already seen
+        for (id, tick) in self.removed.by_ref() {
+            if *tick > self.last_run {
+                return Some(*id);
+            }
+        }
+        return None;
+    }
+}
+
+/** Records which entities lost which components, and at which change tick */
+pub(crate) struct Removals {
+    // The removed entities and the tick they were removed at, per component type
+    removed: HashMap<TypeId, Vec<(EntityId, u64)>>,
+    // The ids of the systems that read the removals, per component type
+    readers: Mutex<HashMap<TypeId, Vec<usize>>>,
+    // The ticks of the latest prunes, the oldest one bounds the removals nobody has read yet
+    prunes: VecDeque<u64>,
+}
+
+impl Removals {
+    /** Construct an empty removal log */
+    pub(crate) fn new() -> Self {
+        return Self {
+            removed: HashMap::new(),
+            readers: Mutex::new(HashMap::new()),
+            prunes: VecDeque::new(),
+        };
+    }
+
+    /** Remember that the system reads the removals of the component type */
+    pub(crate) fn add_reader(&self, component_type: TypeId, system: usize) {
+        let mut readers = self.readers.lock().unwrap_or_else(PoisonError::into_inner);
+        let readers = readers.entry(component_type).or_default();
+        if !readers.contains(&system) {
+            readers.push(system);
+        }
+    }
+
+    /** Record that the entity lost a component of the type at the tick */
+    pub(crate) fn record(&mut self, component_type: TypeId, id: EntityId, change_tick: u64) {
+        self.removed
+            .entry(component_type)
+            .or_default()
+            .push((id, change_tick));
+    }
+
+    /**
+     * Forget the removals every reader of their component type has seen.
+     * Gets the tick a system last ran at by its id, readers that aren't systems of the scene are left out.
+     * Removals of types without readers are kept for the amount of prunes after the one they were made before,
+     * so systems reading them for the first time later on still see them
+     */
+    pub(crate) fn prune<F: Fn(usize) -> Option<u64>>(
+        &mut self,
+        tick: u64,
+        keep: usize,
+        last_run: F,
+    ) {
+        self.prunes.push_back(tick);
+        while self.prunes.len() > keep + 1 {
+            self.prunes.pop_front();
+        }
+        let unread = if self.prunes.len() > keep {
+            self.prunes[0]
+        } else {
+            0
+        };
+        let readers = self
+            .readers
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        for (component_type, removed) in self.removed.iter_mut() {
+            let oldest_run = match readers.get(component_type) {
+                Some(readers) => readers.iter().filter_map(|id| last_run(*id)).min(),
+                None => None,
+            };
+            let oldest_run = oldest_run.unwrap_or(unread);
+            removed.retain(|(_, t)| *t > oldest_run);
+        }
+    }
+}
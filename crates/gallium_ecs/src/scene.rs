@@ -1,104 +1,747 @@
 use std::{
-    cell::{RefCell, RefMut},
+    any::TypeId,
     collections::HashMap,
     fs::File,
     io::Write,
+    sync::{Mutex, MutexGuard, PoisonError},
 };
 
-use crate::{Component, Entity, System, World};
+use crate::{
+    check_query_access, running_system, Archetype, Archetypes, Bundle, Commands, Component,
+    ComponentTicks, Condition, Entities, Entity, EntityId, EntityMap, EntityRef, IntoSystem,
+    Parent, QueryData, QueryFilter, QueryIter, Removals, RemovedComponents, SparseSets,
+    StorageType, System, SystemEntry, SystemOrder, SystemTags, TickedComponent, World,
+};
 use ron::{
     de::from_reader,
     from_str,
     ser::{to_string_pretty, PrettyConfig},
     Error,
 };
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::Error as DeError,
+    ser::{Error as SerError, SerializeSeq, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/** The tag of the systems run once when a scene becomes active */
+pub const STARTUP_TAG: &str = "startup";
+/** The tag of the systems run when a scene stops being active */
+pub const EXIT_TAG: &str = "exit";
 
 /** Represents an ecs scene containing entities */
-#[derive(Serialize, Deserialize)]
 pub struct Scene {
-    systems: Option<HashMap<String, Vec<Box<dyn System>>>>,
-    entities: Vec<RefCell<Entity>>,
+    systems: Option<SystemTags>,
+    // The systems added while the systems run, added once they ran
+    pending_systems: SystemTags,
+    // Whether the startup systems ran, saved so loaded scenes don't run them again
+    started: bool,
+    // The entity allocator and bookkeeping
+    pub(crate) entities: Entities,
+    // The component tables
+    pub(crate) archetypes: Archetypes,
+    // The sparse sets for components that don't live in the tables
+    pub(crate) sparse_sets: SparseSets,
+    // The change tick of the running systems, or of the last systems that ran
+    pub(crate) change_tick: u64,
+    // The entities that lost components, until every system has seen them
+    removals: Removals,
+    // The structural changes queued by systems
+    commands: Mutex<Commands>,
+    // The command buffers of the systems running in parallel, in the system order
+    batch_commands: Vec<(usize, Mutex<Commands>)>,
 }
 
 impl Scene {
     /** Construct a new scene */
     pub fn new() -> Self {
         return Self {
-            systems: Some(HashMap::new()),
-            entities: vec![],
+            systems: Some(SystemTags::new()),
+            pending_systems: SystemTags::new(),
+            started: false,
+            entities: Entities::new(),
+            archetypes: Archetypes::new(),
+            sparse_sets: SparseSets::new(),
+            change_tick: 1,
+            removals: Removals::new(),
+            commands: Mutex::new(Commands::new()),
+            batch_commands: vec![],
         };
     }
 
-    /** Add an entity to the scene */
-    pub fn add_entity(&mut self, mut entity: Entity) {
-        // Set the id
-        entity.id = self.entities.len();
-        // Add the entity
-        self.entities.push(RefCell::new(entity));
+    /**
+     * Add an entity to the scene, returning its id.
+     * References a loaded entity holds to its own saved id are rewritten to the new id, references to other entities become the placeholder id
+     */
+    pub fn add_entity(&mut self, entity: Entity) -> EntityId {
+        let (ids, _) = self.spawn_entities(vec![entity]);
+        return ids[0];
     }
 
-    /** Add a system to the scene */
-    pub fn add_system<T: 'static + System>(mut self, tag: &str, system: T) {
-        let systems = self.systems.as_mut().unwrap();
+    /**
+     * Add entities to the scene, returning the map from the ids the loaded entities were saved with to their new ids.
+     * References between the loaded entities are rewritten through the map, references to entities outside of them become the placeholder id
+     */
+    pub fn add_entities(&mut self, entities: Vec<Entity>) -> EntityMap {
+        let (_, map) = self.spawn_entities(entities);
+        return map;
+    }
 
-        // Add a system to the tag if the tag exists
-        if systems.contains_key(tag) {
-            systems.get_mut(tag).unwrap().push(Box::new(system));
-            return;
+    /** Add entities with fresh ids, rewriting the references between them */
+    fn spawn_entities(&mut self, entities: Vec<Entity>) -> (Vec<EntityId>, EntityMap) {
+        // Allocate all ids first, so references to entities added later can be mapped
+        let mut map = EntityMap::new();
+        let mut ids = vec![];
+        for entity in entities.iter() {
+            let id = self.entities.alloc(entity.is_active);
+            if entity.has_saved_id {
+                map.insert(entity.id, id);
+            }
+            ids.push(id);
+        }
+        // Rewrite the references of the loaded entities and move the components into the scene,
+        // built entities reference entities in the scene already
+        for (id, mut entity) in ids.iter().zip(entities) {
+            if entity.has_saved_id {
+                entity.map_entities(&map);
+            }
+            self.insert_entity(*id, entity);
+        }
+        return (ids, map);
+    }
+
+    /** Move the components of an entity into the storage of an allocated id */
+    fn insert_entity(&mut self, id: EntityId, entity: Entity) {
+        // Route the components to their storage
+        let mut table = vec![];
+        for component in entity.take_components() {
+            match component.storage_type() {
+                StorageType::Table => {
+                    table.push((component, ComponentTicks::new(self.change_tick)))
+                }
+                StorageType::SparseSet => {
+                    self.sparse_sets
+                        .insert_boxed(id.index(), component, self.change_tick)
+                }
+            }
+        }
+        // Move the table components into the archetype tables
+        let location = self.archetypes.insert(id, table);
+        self.entities.set_location(id, location);
+    }
+
+    /** Insert a component into an entity, replacing the existing component of the same type */
+    pub fn insert_component<T: 'static + Component>(&mut self, id: EntityId, component: T) -> bool {
+        let location = match self.entities.get(id) {
+            Some(meta) => meta.location.unwrap(),
+            None => return false,
+        };
+        // Sparse set components don't change the archetype
+        if component.storage_type() == StorageType::SparseSet {
+            let change_tick = self.change_tick;
+            self.sparse_sets
+                .get_or_create::<T>()
+                .insert(id.index(), component, change_tick);
+            return true;
+        }
+        // Replace the component if the entity already has one
+        if let Some(column) = self.archetypes.get(location.archetype).get_column::<T>() {
+            *column.data[location.row]
+                .write()
+                .unwrap_or_else(PoisonError::into_inner) = component;
+            column.ticks[location.row].set_changed(self.change_tick);
+            return true;
+        }
+        // Move the entity to the archetype including the component
+        let ticks = ComponentTicks::new(self.change_tick);
+        self.change_archetype(id, |components| {
+            components.push((Box::new(component), ticks))
+        });
+        return true;
+    }
+
+    /** Insert the components of a bundle into an entity, replacing the existing components of the same types */
+    pub fn insert_bundle<B: Bundle>(&mut self, id: EntityId, bundle: B) -> bool {
+        if self.entities.get(id).is_none() {
+            return false;
+        }
+        let mut components = vec![];
+        bundle.take_components(&mut components);
+        // Sparse set components don't change the archetype
+        let mut table = vec![];
+        for component in components {
+            match component.storage_type() {
+                StorageType::Table => table.push(component),
+                StorageType::SparseSet => {
+                    self.sparse_sets
+                        .insert_boxed(id.index(), component, self.change_tick)
+                }
+            }
+        }
+        if table.is_empty() {
+            return true;
+        }
+        // Move the entity to the archetype including the components
+        let change_tick = self.change_tick;
+        self.change_archetype(id, |components| {
+            for component in table {
+                let component_type = component.as_any().type_id();
+                match components
+                    .iter()
+                    .position(|c| c.0.as_any().type_id() == component_type)
+                {
+                    // Replace the existing component, keeping the tick it was added at
+                    Some(index) => {
+                        let ticks = components[index].1.clone();
+                        ticks.set_changed(change_tick);
+                        components[index] = (component, ticks);
+                    }
+                    None => components.push((component, ComponentTicks::new(change_tick))),
+                }
+            }
+        });
+        return true;
+    }
+
+    /** Remove the component of provided type from an entity, returning it */
+    pub fn remove_component<T: 'static + Component>(&mut self, id: EntityId) -> Option<T> {
+        let location = self.entities.get(id)?.location.unwrap();
+        // Remove the component from its sparse set
+        if let Some(set) = self.sparse_sets.get_mut::<T>() {
+            if let Some(component) = set.remove(id.index()) {
+                self.removals
+                    .record(TypeId::of::<T>(), id, self.change_tick);
+                return Some(component);
+            }
+        }
+        if !self.archetypes.get(location.archetype).has_component::<T>() {
+            return None;
+        }
+        // Move the entity to the archetype without the component
+        let mut removed = None;
+        self.change_archetype(id, |components| {
+            let index = components
+                .iter()
+                .position(|c| c.0.as_any().is::<T>())
+                .unwrap();
+            removed = Some(components.remove(index).0);
+        });
+        self.removals
+            .record(TypeId::of::<T>(), id, self.change_tick);
+        return match removed.unwrap().into_any().downcast::<T>() {
+            Ok(c) => Some(*c),
+            Err(_) => None,
+        };
+    }
+
+    /** Move a living entity to the archetype matching its changed table components */
+    fn change_archetype<F: FnOnce(&mut Vec<TickedComponent>)>(&mut self, id: EntityId, change: F) {
+        let location = self.entities.get(id).unwrap().location.unwrap();
+        // Take the components out of the current archetype
+        let archetype = self.archetypes.get_mut(location.archetype);
+        let (mut components, moved) = archetype.swap_remove(location.row);
+        if let Some(moved) = moved {
+            self.entities.set_location(moved, location);
+        }
+        // Insert the changed components into the new archetype
+        change(&mut components);
+        let location = self.archetypes.insert(id, components);
+        self.entities.set_location(id, location);
+    }
+
+    /** Despawn an entity, returns false if the entity doesn't exist */
+    pub fn despawn(&mut self, id: EntityId) -> bool {
+        if self.entities.get(id).is_none() {
+            return false;
+        }
+        // Detach the entity from the hierarchy, its children lose their parent
+        self.remove_parent(id);
+        for child in self.get_children(id) {
+            self.remove_component::<Parent>(child);
+        }
+        // Detaching can move the entity to another archetype
+        let location = self.entities.get(id).unwrap().location.unwrap();
+        // Remove the table components, the last entity in the archetype takes its row
+        let archetype = self.archetypes.get_mut(location.archetype);
+        let (components, moved) = archetype.swap_remove(location.row);
+        if let Some(moved) = moved {
+            self.entities.set_location(moved, location);
+        }
+        for (component, _) in components {
+            self.removals
+                .record(component.as_any().type_id(), id, self.change_tick);
+        }
+        // Remove the sparse set components
+        for set in self.sparse_sets.iter_mut() {
+            if set.remove_boxed(id.index()).is_some() {
+                self.removals
+                    .record(set.component_type(), id, self.change_tick);
+            }
+        }
+        // Free the id
+        return self.entities.free(id);
+    }
+
+    /** Despawn all entities matching the predicate, returns the amount of despawned entities */
+    pub fn despawn_where<F: FnMut(&EntityRef) -> bool>(&mut self, mut predicate: F) -> usize {
+        // Collect the ids first, since the entity references borrow the scene
+        let ids: Vec<EntityId> = self
+            .get_entities()
+            .iter()
+            .filter(|e| predicate(e))
+            .map(|e| e.id())
+            .collect();
+        for id in ids.iter() {
+            self.despawn(*id);
+        }
+        return ids.len();
+    }
+
+    /**
+     * Move the entities of another scene into the scene with fresh ids, returning the map from their old ids to their new ids.
+     * References between the entities are rewritten, the systems are added to the scene's tags if merge_systems is set.
+     * Systems merged while the systems run are added once they ran.
+     * Merged startup systems only run if the scene hasn't started yet, a started scene has to run them by hand.
+     * Panics before changing the scene if the merged systems make the system order invalid
+     */
+    pub fn merge(&mut self, mut other: Scene, merge_systems: bool) -> EntityMap {
+        if let (true, Some(other_systems)) = (merge_systems, other.systems.take()) {
+            match self.systems.as_mut() {
+                Some(systems) => {
+                    if let Err(e) = systems.try_append(other_systems) {
+                        panic!("{}", e);
+                    }
+                }
+                None => self.pending_systems.append(other_systems),
+            }
         }
-        // Create a tag and add the system
-        systems.insert(String::from(tag), vec![Box::new(system)]);
+        return self.add_entities(other.into_entities());
     }
 
-    /** Get an entity by its id */
-    pub fn get_entity(&self, id: usize) -> Option<RefMut<Entity>> {
-        if let Some(e) = self.entities.get(id) {
-            return Some(e.borrow_mut());
+    /**
+     * Copy the entities of another scene into the scene with fresh ids, returning the map from their old ids to their new ids.
+     * The scene is copied through ron, so it fails if a component of the other scene is borrowed
+     */
+    pub fn instantiate(&mut self, other: &Scene, merge_systems: bool) -> Result<EntityMap, Error> {
+        let copy = Scene::from_ron(other.to_ron()?)?;
+        return Ok(self.merge(copy, merge_systems));
+    }
+
+    /** Move the entities out of the scene, keeping their ids as saved ids */
+    fn into_entities(mut self) -> Vec<Entity> {
+        let ids: Vec<EntityId> = self.entities.iter().collect();
+        let mut entities = vec![];
+        for id in ids {
+            let meta = self.entities.get(id).unwrap();
+            let location = meta.location.unwrap();
+            let is_active = meta.is_active();
+            // Take the table components, the last entity in the archetype takes its row
+            let archetype = self.archetypes.get_mut(location.archetype);
+            let (components, moved) = archetype.swap_remove(location.row);
+            if let Some(moved) = moved {
+                self.entities.set_location(moved, location);
+            }
+            let mut components: Vec<Box<dyn Component>> =
+                components.into_iter().map(|(c, _)| c).collect();
+            // Take the sparse set components
+            for set in self.sparse_sets.iter_mut() {
+                if let Some(component) = set.remove_boxed(id.index()) {
+                    components.push(component);
+                }
+            }
+            entities.push(Entity::from_components(id, is_active, components));
         }
-        return None;
+        return entities;
+    }
+
+    /**
+     * Add a system to the scene.
+     * Returns an error without adding the system if it makes the system order contain a cycle
+     */
+    pub fn add_system<M, T: IntoSystem<M>>(&mut self, tag: &str, system: T) -> Result<(), String> {
+        return self.push_system(
+            tag,
+            SystemEntry::new(system.into_system(), SystemOrder::new(), None),
+        );
+    }
+
+    /**
+     * Add a system to the scene with a label and constraints on the systems it runs before or after.
+     * Returns an error without adding the system if the order contains a cycle or refers to a label no system has
+     */
+    pub fn add_ordered_system<M, T: IntoSystem<M>>(
+        &mut self,
+        tag: &str,
+        system: T,
+        order: SystemOrder,
+    ) -> Result<(), String> {
+        return self.push_system(tag, SystemEntry::new(system.into_system(), order, None));
+    }
+
+    /** Add a system to the scene that only ticks when the condition holds, returns an error without adding the system if the order contains a cycle */
+    pub fn add_system_if<M, T: IntoSystem<M>, C: 'static + Condition>(
+        &mut self,
+        tag: &str,
+        system: T,
+        condition: C,
+    ) -> Result<(), String> {
+        let condition: Box<dyn Condition> = Box::new(condition);
+        let entry = SystemEntry::new(system.into_system(), SystemOrder::new(), Some(condition));
+        return self.push_system(tag, entry);
+    }
+
+    /** Add a system that runs once when the scene becomes active */
+    pub fn add_startup_system<M, T: IntoSystem<M>>(&mut self, system: T) -> Result<(), String> {
+        return self.add_system(STARTUP_TAG, system);
+    }
+
+    /** Add a system that runs when the scene stops being active */
+    pub fn add_exit_system<M, T: IntoSystem<M>>(&mut self, system: T) -> Result<(), String> {
+        return self.add_system(EXIT_TAG, system);
+    }
+
+    /**
+     * Add a system entry to the tag, creating the tag if needed.
+     * Systems added while the systems run are added once they ran, their order is checked then
+     */
+    fn push_system(&mut self, tag: &str, entry: SystemEntry) -> Result<(), String> {
+        match self.systems.as_mut() {
+            Some(systems) => return systems.try_push(tag, entry),
+            None => self.pending_systems.push(tag, entry),
+        }
+        return Ok(());
+    }
+
+    /** Get an entity by its id, returns none if the entity doesn't exist anymore */
+    pub fn get_entity(&self, id: EntityId) -> Option<EntityRef<'_>> {
+        self.entities.get(id)?;
+        return Some(EntityRef::new(self, id));
     }
 
     /** Get a list of entities */
-    pub fn get_entities(&self) -> Vec<RefMut<Entity>> {
-        return self.entities.iter().map(|e| e.borrow_mut()).collect();
+    pub fn get_entities(&self) -> Vec<EntityRef<'_>> {
+        return self
+            .entities
+            .iter()
+            .map(|id| EntityRef::new(self, id))
+            .collect();
+    }
+
+    /**
+     * Query the active entities for components, yielding their ids with the fetched components.
+     * Components requested as &T are borrowed shared, components requested as &mut T exclusively.
+     * Panics if the query borrows a component mutably while also borrowing it elsewhere, use try_query to get the error instead
+     */
+    pub fn query<'a, Q: QueryData<'a>>(&'a self) -> QueryIter<'a, Q> {
+        return self.query_filtered();
+    }
+
+    /** Query the active entities for components, only including entities matching the filter */
+    pub fn query_filtered<'a, Q: QueryData<'a>, F: QueryFilter<'a>>(
+        &'a self,
+    ) -> QueryIter<'a, Q, F> {
+        return match self.try_query_filtered() {
+            Ok(query) => query,
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    /** Query the active entities for components, returns an error if the query borrows a component mutably while also borrowing it elsewhere */
+    pub fn try_query<'a, Q: QueryData<'a>>(&'a self) -> Result<QueryIter<'a, Q>, String> {
+        return self.try_query_filtered();
+    }
+
+    /** Query the active entities matching the filter for components, returns an error if the query borrows a component mutably while also borrowing it elsewhere */
+    pub fn try_query_filtered<'a, Q: QueryData<'a>, F: QueryFilter<'a>>(
+        &'a self,
+    ) -> Result<QueryIter<'a, Q, F>, String> {
+        check_query_access::<Q>()?;
+        return Ok(QueryIter::new(self));
+    }
+
+    /**
+     * Get the entities that lost a component of type T since the running system last ran.
+     * Removals are kept until every system that has read removals of the type has seen them
+     */
+    pub fn removed<T: 'static + Component>(&self) -> RemovedComponents<'_, T> {
+        if let Some((id, _)) = running_system() {
+            self.removals.add_reader(TypeId::of::<T>(), id);
+        }
+        return RemovedComponents::new(&self.removals, self.last_change_tick());
+    }
+
+    /**
+     * Get the command buffer, the queued commands are applied after the running systems.
+     * Systems running in parallel each get their own buffer, the buffers are applied in the system order
+     */
+    pub fn commands(&self) -> MutexGuard<'_, Commands> {
+        let buffer = match running_system() {
+            Some((id, _)) => self.batch_commands.iter().find(|(s, _)| *s == id),
+            None => None,
+        };
+        return match buffer {
+            Some((_, commands)) => commands,
+            None => &self.commands,
+        }
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    }
+
+    /** Apply the queued commands, including the commands they queue themselves */
+    pub fn apply_commands(&mut self, world: &mut World) {
+        loop {
+            let commands = self
+                .commands
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            if commands.is_empty() {
+                return;
+            }
+            for command in commands {
+                command(self, world);
+            }
+        }
+    }
+
+    /** Get the change tick of the running systems, or of the last systems that ran */
+    pub fn change_tick(&self) -> u64 {
+        return self.change_tick;
+    }
+
+    /** Get the change tick the system running on this thread last ran at, outside of systems every change is seen */
+    pub fn last_change_tick(&self) -> u64 {
+        return match running_system() {
+            Some((_, last_run)) => last_run,
+            None => 0,
+        };
+    }
+
+    /** Get the archetype tables the components are stored in */
+    pub fn archetypes(&self) -> impl Iterator<Item = &Archetype> {
+        return self.archetypes.iter();
+    }
+
+    /** Check if the scene has systems with the tag */
+    pub fn has_systems(&self, tag: &str) -> bool {
+        return match self.systems.as_ref() {
+            Some(systems) => systems.contains(tag),
+            None => false,
+        };
+    }
+
+    /** Get the labels of the systems with the tag, in the order they run */
+    pub fn system_labels(&self, tag: &str) -> Vec<String> {
+        return match self.systems.as_ref() {
+            Some(systems) => systems.labels(tag).into_iter().map(String::from).collect(),
+            None => vec![],
+        };
+    }
+
+    /** Get the labels of the systems with the tag grouped into the batches they run in, in the order they run */
+    pub fn system_batches(&self, tag: &str) -> Vec<Vec<String>> {
+        let systems = match self.systems.as_ref() {
+            Some(systems) => systems,
+            None => return vec![],
+        };
+        return systems
+            .batches(tag)
+            .into_iter()
+            .map(|batch| {
+                batch
+                    .into_iter()
+                    .map(|(t, i)| String::from(systems.get(t, i).label()))
+                    .collect()
+            })
+            .collect();
+    }
+
+    /**
+     * Check that no system has to run before a system in an earlier stage of the schedule version.
+     * Systems that were already checked against the version since they last changed are skipped
+     */
+    pub(crate) fn check_schedule(&mut self, stages: &[String], version: u64) -> Result<(), String> {
+        return match self.systems.as_mut() {
+            Some(systems) => systems.check_schedule(stages, version),
+            None => Ok(()),
+        };
+    }
+
+    /** Check if the startup systems of the scene ran */
+    pub fn has_started(&self) -> bool {
+        return self.started;
+    }
+
+    /** Run the startup systems again the next time the scene becomes active */
+    pub fn restart(&mut self) {
+        self.started = false;
+    }
+
+    /** Run the startup systems unless they already ran, called by World::set_scene */
+    pub fn start(&mut self, world: &mut World) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        if self.has_systems(STARTUP_TAG) {
+            self.tick_systems(STARTUP_TAG, world);
+        }
+    }
+
+    /** Run the exit systems, called by World when the scene stops being active */
+    pub fn exit(&mut self, world: &mut World) {
+        if self.has_systems(EXIT_TAG) {
+            self.tick_systems(EXIT_TAG, world);
+        }
     }
 
     /** Tick the systems with specified tag in the scene */
     pub fn tick_systems(&mut self, tag: &str, world: &mut World) {
-        // Take ownership of the systems
-        let mut systems = self.systems.take().unwrap();
-
         // Check if any system has specified tag
-        if !systems.contains_key(tag) {
+        if !self.systems.as_ref().unwrap().contains(tag) {
             println!("Scene doesnt include system with secified tag: {}", tag);
             return;
         }
 
-        // Loop over systems
-        for system in systems.get_mut(tag).unwrap().iter_mut() {
-            system.tick(self, world);
+        // Take ownership of the systems
+        let mut systems = self.systems.take().unwrap();
+
+        // Loop over the batches of systems in order, leaving out systems whose condition fails
+        for batch in systems.batches(tag) {
+            let batch: Vec<(usize, usize)> = batch
+                .into_iter()
+                .filter(|(t, i)| systems.get_mut(*t, *i).should_run(self, world))
+                .collect();
+            if batch.is_empty() {
+                continue;
+            }
+            let (t, i) = batch[0];
+            if systems.get(t, i).access.is_none() {
+                self.run_system(systems.get_mut(t, i), world, |system, scene, world| {
+                    system.tick(scene, world)
+                });
+            } else {
+                self.run_parallel(&mut systems, &batch, world);
+            }
         }
 
+        // Changes made outside of systems are seen by every system
+        self.change_tick = world.increment_change_tick();
+
+        // Forget the removals every system has seen
+        self.prune_removals(&systems);
+
         // Return ownership of systems
+        self.return_systems(systems);
+    }
+
+    /** Return the systems taken to run them, adding the systems added while they ran. Panics after returning the systems if the added systems make the order invalid */
+    fn return_systems(&mut self, mut systems: SystemTags) {
+        let pending = std::mem::replace(&mut self.pending_systems, SystemTags::new());
+        let result = if pending.entries().next().is_some() {
+            systems.try_append(pending)
+        } else {
+            Ok(())
+        };
         self.systems = Some(systems);
+        if let Err(e) = result {
+            panic!("{}", e);
+        }
+    }
+
+    /** Run a system, letting it see the changes made since it last ran */
+    fn run_system<F: FnOnce(&mut dyn System, &mut Scene, &mut World)>(
+        &mut self,
+        entry: &mut SystemEntry,
+        world: &mut World,
+        run: F,
+    ) {
+        self.change_tick = world.increment_change_tick();
+        entry.run_as(|system| run(system, self, world));
+        // Apply the structural changes the system queued
+        self.apply_commands(world);
+        entry.last_run = self.change_tick;
+    }
+
+    /** Run a batch of systems declaring their access, each on its own thread if there are several */
+    fn run_parallel(
+        &mut self,
+        systems: &mut SystemTags,
+        batch: &[(usize, usize)],
+        world: &mut World,
+    ) {
+        // The systems share the change tick, only their last run differs
+        self.change_tick = world.increment_change_tick();
+        let mut entries = systems.get_many_mut(batch);
+        self.batch_commands = entries
+            .iter()
+            .map(|entry| (entry.id, Mutex::new(Commands::new())))
+            .collect();
+        let (scene, world_ref): (&Scene, &World) = (self, world);
+        if let [entry] = &mut entries[..] {
+            entry.run_as(|system| system.tick_parallel(scene, world_ref));
+        } else {
+            std::thread::scope(|s| {
+                for entry in entries.iter_mut() {
+                    s.spawn(move || entry.run_as(|system| system.tick_parallel(scene, world_ref)));
+                }
+            });
+        }
+        for entry in entries {
+            entry.last_run = self.change_tick;
+        }
+        // Queue the commands of the systems in the system order
+        for (_, mut commands) in std::mem::take(&mut self.batch_commands) {
+            let commands = commands.get_mut().unwrap_or_else(PoisonError::into_inner);
+            self.commands
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner)
+                .append(commands);
+        }
+        // Apply the structural changes the systems queued at a later tick, so the systems see each other's changes
+        self.change_tick = world.increment_change_tick();
+        self.apply_commands(world);
+    }
+
+    /**
+     * Forget the removals every system reading them has seen.
+     * Removals nobody has read yet are kept until every tag ran once more, startup and exit systems aside
+     */
+    fn prune_removals(&mut self, systems: &SystemTags) {
+        let last_runs: HashMap<usize, u64> = systems
+            .entries()
+            .map(|entry| (entry.id, entry.last_run))
+            .collect();
+        let keep = systems
+            .tags()
+            .filter(|tag| *tag != STARTUP_TAG && *tag != EXIT_TAG)
+            .count()
+            .max(1);
+        self.removals
+            .prune(self.change_tick, keep, |id| last_runs.get(&id).copied());
     }
 
     /** Dispatch a scene over the systems */
     pub fn dispatch_event(&mut self, tag: &str, world: &mut World, data: &dyn std::any::Any) {
         // Take ownership of the systems
-        let systems = self.systems.take().unwrap();
+        let mut systems = self.systems.take().unwrap();
 
-        // Loop over systems
-        for system_list in systems.iter() {
-            for system in system_list.1.iter() {
-                // Handle events in system
-                system.on_event(self, world, tag, data);
-            }
+        // Loop over systems in order
+        for (t, i) in systems.ordered(None) {
+            // Handle events in system
+            self.run_system(systems.get_mut(t, i), world, |system, scene, world| {
+                system.on_event(scene, world, tag, data)
+            });
         }
 
+        // Changes made outside of systems are seen by every system
+        self.change_tick = world.increment_change_tick();
+
+        // Forget the removals every system has seen
+        self.prune_removals(&systems);
+
         // Return ownership of systems
-        self.systems = Some(systems);
+        self.return_systems(systems);
     }
 
     /** Serialize the scene to a ron string */
@@ -133,10 +776,120 @@ impl Scene {
     }
 }
 
+impl Serialize for Scene {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Scene", 4)?;
+        state.serialize_field("systems", &self.systems)?;
+        state.serialize_field("started", &self.started)?;
+        state.serialize_field("entities", &SerializedEntities { scene: self })?;
+        // Save the generations of the free slots, so ids to despawned entities stay invalid
+        let free: Vec<EntityId> = self.entities.free_slots().collect();
+        state.serialize_field("free", &free)?;
+        return state.end();
+    }
+}
+
+impl<'de> Deserialize<'de> for Scene {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = SceneData::deserialize(deserializer)?;
+        // Rebuild the archetype tables from the entities
+        let mut scene = Scene::new();
+        scene.systems = data.systems;
+        scene.started = data.started;
+        if let Some(systems) = scene.systems.as_mut() {
+            systems.update_order().map_err(D::Error::custom)?;
+        }
+        for entity in data.entities {
+            // Keep the saved ids so references to them stay valid
+            if !scene.entities.alloc_at(entity.id, entity.is_active) {
+                return Err(D::Error::custom(format!(
+                    "Duplicate entity id: {}",
+                    entity.id
+                )));
+            }
+            scene.insert_entity(entity.id, entity);
+        }
+        for id in data.free.iter() {
+            if !scene.entities.free_at(*id) {
+                return Err(D::Error::custom(format!("Free entity id is taken: {}", id)));
+            }
+        }
+        // Older files don't save the free slots, so ids to them could match the next entity
+        scene.entities.invalidate_free(&data.free);
+        return Ok(scene);
+    }
+}
+
+/** The serialized layout of a scene */
+#[derive(Deserialize)]
+#[serde(rename = "Scene")]
+struct SceneData {
+    systems: Option<SystemTags>,
+    #[serde(default)]
+    started: bool,
+    entities: Vec<Entity>,
+    #[serde(default)]
+    free: Vec<EntityId>,
+}
+
+/** Serializes the entities of a scene in the same layout as a standalone entity */
+struct SerializedEntities<'a> {
+    scene: &'a Scene,
+}
+
+impl Serialize for SerializedEntities<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.scene.entities.iter().count()))?;
+        for id in self.scene.entities.iter() {
+            let meta = self.scene.entities.get(id).unwrap();
+            let location = meta.location.unwrap();
+            let archetype = self.scene.archetypes.get(location.archetype);
+            let mut stored_components = vec![];
+            let mut components = vec![];
+            // Borrow the table components of the entity
+            for column in archetype.columns() {
+                match column.try_get_dyn(location.row) {
+                    Some(c) => components.push(c),
+                    None => return Err(S::Error::custom("already mutably borrowed")),
+                }
+                stored_components.push(column.component_name());
+            }
+            // Borrow the sparse set components of the entity
+            for set in self.scene.sparse_sets.iter() {
+                if !set.contains(id.index()) {
+                    continue;
+                }
+                match set.try_get_dyn(id.index()) {
+                    Some(c) => components.push(c),
+                    None => return Err(S::Error::custom("already mutably borrowed")),
+                }
+                stored_components.push(set.component_name());
+            }
+            seq.serialize_element(&SerializedEntity {
+                id,
+                is_active: meta.is_active(),
+                stored_components,
+                components: components.iter().map(|c| &**c).collect(),
+            })?;
+        }
+        return seq.end();
+    }
+}
+
+/** A borrowed entity in the serialized layout of a standalone entity */
+#[derive(Serialize)]
+#[serde(rename = "Entity")]
+struct SerializedEntity<'a> {
+    id: EntityId,
+    is_active: bool,
+    stored_components: Vec<&'static str>,
+    components: Vec<&'a dyn Component>,
+}
+
 /** Builds the scene */
 pub struct SceneBuilder {
-    systems: HashMap<String, Vec<Box<dyn System>>>,
-    entities: Vec<RefCell<Entity>>,
+    systems: SystemTags,
+    entities: Vec<Entity>,
 }
 
 impl SceneBuilder {
@@ -144,40 +897,84 @@ impl SceneBuilder {
     pub fn new() -> Self {
         return Self {
             entities: vec![],
-            systems: HashMap::new(),
+            systems: SystemTags::new(),
         };
     }
 
     /** Add an entity to the scene */
-    pub fn with_entity(mut self, mut entity: Entity) -> Self {
-        // Set the id
-        entity.id = self.entities.len();
-        // Add the entity
-        self.entities.push(RefCell::new(entity));
+    pub fn with_entity(mut self, entity: Entity) -> Self {
+        self.entities.push(entity);
         return self;
     }
 
     /** Add a system to the scene */
-    pub fn with_system<T: 'static + System>(mut self, tag: &str, system: T) -> Self {
-        // Add a system to the tag if the tag exists
-        if self.systems.contains_key(tag) {
-            self.systems.get_mut(tag).unwrap().push(Box::new(system));
-            return self;
-        }
-        // Create a tag and add the system
+    pub fn with_system<M, T: IntoSystem<M>>(self, tag: &str, system: T) -> Self {
+        return self.with_ordered_system(tag, system, SystemOrder::new());
+    }
+
+    /** Add a system to the scene with a label and constraints on the systems it runs before or after */
+    pub fn with_ordered_system<M, T: IntoSystem<M>>(
+        mut self,
+        tag: &str,
+        system: T,
+        order: SystemOrder,
+    ) -> Self {
+        // Add the system to the tag, creating the tag if needed
         self.systems
-            .insert(String::from(tag), vec![Box::new(system)]);
+            .push(tag, SystemEntry::new(system.into_system(), order, None));
+        return self;
+    }
+
+    /** Add a system that runs once when the scene becomes active */
+    pub fn with_startup_system<M, T: IntoSystem<M>>(self, system: T) -> Self {
+        return self.with_system(STARTUP_TAG, system);
+    }
+
+    /** Add a system that runs when the scene stops being active */
+    pub fn with_exit_system<M, T: IntoSystem<M>>(self, system: T) -> Self {
+        return self.with_system(EXIT_TAG, system);
+    }
+
+    /** Add a system to the scene that only ticks when the condition holds */
+    pub fn with_system_if<M, T: IntoSystem<M>, C: 'static + Condition>(
+        self,
+        tag: &str,
+        system: T,
+        condition: C,
+    ) -> Self {
+        return self.with_ordered_system_if(tag, system, SystemOrder::new(), condition);
+    }
 
+    /** Add an ordered system to the scene that only ticks when the condition holds */
+    pub fn with_ordered_system_if<M, T: IntoSystem<M>, C: 'static + Condition>(
+        mut self,
+        tag: &str,
+        system: T,
+        order: SystemOrder,
+        condition: C,
+    ) -> Self {
+        let entry = SystemEntry::new(system.into_system(), order, Some(Box::new(condition)));
+        self.systems.push(tag, entry);
         return self;
     }
 
-    /** Build the scene */
+    /** Build the scene, panics if the system order contains a cycle or refers to a label no system has */
     pub fn build(self) -> Scene {
-        return Scene {
-            entities: self.entities,
-            systems: Some(self.systems),
+        return match self.try_build() {
+            Ok(scene) => scene,
+            Err(e) => panic!("{}", e),
         };
     }
+
+    /** Build the scene, returns an error if the system order contains a cycle or refers to a label no system has */
+    pub fn try_build(mut self) -> Result<Scene, String> {
+        self.systems.update_order()?;
+        let mut scene = Scene::new();
+        scene.systems = Some(self.systems);
+        // Move the entities into the scene together, so references between them are rewritten
+        scene.add_entities(self.entities);
+        return Ok(scene);
+    }
 }
 
 /** A trait for entity list's */
@@ -186,28 +983,36 @@ pub trait EntityList {
     fn are_active(self) -> Self;
     /** Remove entitites that dont have the provided component from the list */
     fn with_component<T: 'static + Component>(self) -> Self;
+    /** Remove entitites that have the provided component from the list */
+    fn without_component<T: 'static + Component>(self) -> Self;
+    /** Remove entities that dont match the provided query filter from the list */
+    fn matching<F: for<'a> QueryFilter<'a>>(self) -> Self;
 }
 
-impl EntityList for Vec<RefMut<'_, Entity>> {
+impl EntityList for Vec<EntityRef<'_>> {
     /** Remove entities that arent active from the list */
     fn are_active(self) -> Self {
+        return self.into_iter().filter(|e| e.is_active()).collect();
+    }
+
+    /** Remove entitites that dont have the provided component from the list */
+    fn with_component<T: 'static + Component>(self) -> Self {
         return self
             .into_iter()
-            .filter_map(move |e| if e.is_active { Some(e) } else { None })
+            .filter(|e| e.has_component::<T>())
             .collect();
     }
 
-    /** Remove entitites that dont have the provided component from the list */
-    fn with_component<T: 'static + Component>(self) -> Self {
+    /** Remove entitites that have the provided component from the list */
+    fn without_component<T: 'static + Component>(self) -> Self {
         return self
             .into_iter()
-            .filter_map(move |e| {
-                if e.has_component::<T>() {
-                    Some(e)
-                } else {
-                    None
-                }
-            })
+            .filter(|e| !e.has_component::<T>())
             .collect();
     }
+
+    /** Remove entities that dont match the provided query filter from the list */
+    fn matching<F: for<'a> QueryFilter<'a>>(self) -> Self {
+        return self.into_iter().filter(|e| e.matches::<F>()).collect();
+    }
 }
@@ -0,0 +1,226 @@
+use crate::Scene;
+
+/**
+ * How the scenes below a pushed scene behave while it is on top of them.
+ * By default the scenes below are frozen, they neither tick nor receive events
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LayerMode {
+    // Whether the scenes below keep ticking
+    tick_below: bool,
+    // Whether the scenes below receive the events from dispatch_event
+    events_below: bool,
+}
+
+impl LayerMode {
+    /** Construct a mode that freezes the scenes below */
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /** Construct a mode that freezes the scenes below, like a pause menu */
+    pub fn frozen() -> Self {
+        return Self::new();
+    }
+
+    /** Construct a mode that lets the scenes below tick and receive events, like a hud */
+    pub fn overlay() -> Self {
+        return Self::new().tick_below().events_below();
+    }
+
+    /** Keep ticking the scenes below */
+    pub fn tick_below(mut self) -> Self {
+        self.tick_below = true;
+        return self;
+    }
+
+    /** Keep dispatching events to the scenes below */
+    pub fn events_below(mut self) -> Self {
+        self.events_below = true;
+        return self;
+    }
+
+    /** Check if the scenes below keep ticking */
+    pub fn ticks_below(&self) -> bool {
+        return self.tick_below;
+    }
+
+    /** Check if the scenes below keep receiving events */
+    pub fn dispatches_below(&self) -> bool {
+        return self.events_below;
+    }
+}
+
+/** A scene of the world, with the mode it was pushed with if it is on the stack */
+struct SceneLayer {
+    // Identifies the layer while its scene is taken by a system run
+    id: u64,
+    // The scene, none while its systems run
+    scene: Option<Scene>,
+    // How the scenes below behave
+    mode: LayerMode,
+}
+
+/** The scenes of a world, a stack of layers with the last scene on top and the named scenes alongside it */
+pub(crate) struct WorldScenes {
+    // The layers from the bottom to the top
+    layers: Vec<SceneLayer>,
+    // The named scenes in the order they were added
+    named: Vec<(String, SceneLayer)>,
+    // The id of the next added layer
+    next_id: u64,
+}
+
+impl WorldScenes {
+    /** Construct an empty stack without named scenes */
+    pub fn new() -> Self {
+        return Self {
+            layers: vec![],
+            named: vec![],
+            next_id: 0,
+        };
+    }
+
+    /** Push a scene on top, returning the id of its layer */
+    pub fn push(&mut self, scene: Scene, mode: LayerMode) -> u64 {
+        let layer = self.new_layer(scene, mode);
+        let id = layer.id;
+        self.layers.push(layer);
+        return id;
+    }
+
+    /** Remove the top layer, returning its scene unless it is taken */
+    pub fn pop(&mut self) -> Option<Option<Scene>> {
+        return self.layers.pop().map(|layer| layer.scene);
+    }
+
+    /** Add a named scene, returning the id of its layer, panics if the name is already used */
+    pub fn add_named(&mut self, name: &str, scene: Scene) -> u64 {
+        if self.named_id(name).is_some() {
+            panic!("World already has scene named: {}", name);
+        }
+        let layer = self.new_layer(scene, LayerMode::new());
+        let id = layer.id;
+        self.named.push((String::from(name), layer));
+        return id;
+    }
+
+    /** Remove a named scene, returning its scene unless it is taken */
+    pub fn remove_named(&mut self, name: &str) -> Option<Option<Scene>> {
+        let index = self.named.iter().position(|(n, _)| n == name)?;
+        return Some(self.named.remove(index).1.scene);
+    }
+
+    /** Get the id of the layer of a named scene */
+    pub fn named_id(&self, name: &str) -> Option<u64> {
+        return self
+            .named
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, layer)| layer.id);
+    }
+
+    /** Get the ids of the named scenes in the order they were added */
+    pub fn named(&self) -> Vec<u64> {
+        return self.named.iter().map(|(_, layer)| layer.id).collect();
+    }
+
+    /** Get the names of the named scenes in the order they were added */
+    pub fn names(&self) -> Vec<&str> {
+        return self.named.iter().map(|(name, _)| name.as_str()).collect();
+    }
+
+    /** Get the amount of layers on the stack */
+    pub fn len(&self) -> usize {
+        return self.layers.len();
+    }
+
+    /** Check if the stack has no layers */
+    pub fn is_empty(&self) -> bool {
+        return self.layers.is_empty();
+    }
+
+    /** Check if there are no scenes on the stack or named */
+    pub fn has_no_scenes(&self) -> bool {
+        return self.layers.is_empty() && self.named.is_empty();
+    }
+
+    /** Get the scene of the layer, none if the layer was removed or its scene is taken */
+    pub fn get(&self, id: u64) -> Option<&Scene> {
+        return self.all().find(|l| l.id == id)?.scene.as_ref();
+    }
+
+    /** Get the scene of the layer mutably */
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut Scene> {
+        return self.all_mut().find(|l| l.id == id)?.scene.as_mut();
+    }
+
+    /** Get the layers on the stack and of the named scenes */
+    pub fn ids(&self) -> Vec<u64> {
+        return self.all().map(|l| l.id).collect();
+    }
+
+    /** Get the layers that tick, from the bottom to the top */
+    pub fn ticked(&self) -> Vec<u64> {
+        let mut ids = self.reached(|mode| mode.tick_below);
+        ids.reverse();
+        return ids;
+    }
+
+    /** Get the layers that receive events, from the top to the bottom */
+    pub fn dispatched(&self) -> Vec<u64> {
+        return self.reached(|mode| mode.events_below);
+    }
+
+    /** Take the scene of the layer to run its systems */
+    pub fn take(&mut self, id: u64) -> Option<Scene> {
+        return self.all_mut().find(|l| l.id == id)?.scene.take();
+    }
+
+    /** Return a taken scene to its layer, giving it back if the layer was removed */
+    pub fn restore(&mut self, id: u64, scene: Scene) -> Option<Scene> {
+        return match self.all_mut().find(|l| l.id == id) {
+            Some(layer) if layer.scene.is_none() => {
+                layer.scene = Some(scene);
+                None
+            }
+            _ => Some(scene),
+        };
+    }
+
+    /** Construct a layer with a new id */
+    fn new_layer(&mut self, scene: Scene, mode: LayerMode) -> SceneLayer {
+        let id = self.next_id;
+        self.next_id += 1;
+        return SceneLayer {
+            id,
+            scene: Some(scene),
+            mode,
+        };
+    }
+
+    /** Iterate over the layers on the stack and of the named scenes */
+    fn all(&self) -> impl Iterator<Item = &SceneLayer> {
+        return self.layers.iter().chain(self.named.iter().map(|(_, l)| l));
+    }
+
+    /** Iterate over the layers on the stack and of the named scenes mutably */
+    fn all_mut(&mut self) -> impl Iterator<Item = &mut SceneLayer> {
+        return self
+            .layers
+            .iter_mut()
+            .chain(self.named.iter_mut().map(|(_, l)| l));
+    }
+
+    /** Get the layers from the top down, as far as the modes of the layers above let through */
+    fn reached<F: Fn(&LayerMode) -> bool>(&self, lets_through: F) -> Vec<u64> {
+        let mut ids = vec![];
+        for layer in self.layers.iter().rev() {
+            ids.push(layer.id);
+            if !lets_through(&layer.mode) {
+                break;
+            }
+        }
+        return ids;
+    }
+}
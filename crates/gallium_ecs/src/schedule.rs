@@ -0,0 +1,66 @@
+use std::fmt;
+
+/**
+ * The stages a world runs each time its schedule runs, in order.
+ * Each stage runs the systems of the current scene with the tag of the same name
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schedule {
+    // The names of the stages, in the order they run
+    stages: Vec<String>,
+}
+
+impl Schedule {
+    /** Construct a schedule without stages */
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /** Add a stage that runs after the existing stages */
+    pub fn with_stage(mut self, stage: &str) -> Self {
+        self.insert_stage(self.stages.len(), stage);
+        return self;
+    }
+
+    /** Add a stage that runs right before an existing stage */
+    pub fn with_stage_before(mut self, stage: &str, before: &str) -> Self {
+        let index = self.stage_index(before);
+        self.insert_stage(index, stage);
+        return self;
+    }
+
+    /** Add a stage that runs right after an existing stage */
+    pub fn with_stage_after(mut self, stage: &str, after: &str) -> Self {
+        let index = self.stage_index(after);
+        self.insert_stage(index + 1, stage);
+        return self;
+    }
+
+    /** Get the names of the stages, in the order they run */
+    pub fn stages(&self) -> &[String] {
+        return &self.stages;
+    }
+
+    /** Insert a stage at the index, panics if the stage already exists */
+    fn insert_stage(&mut self, index: usize, stage: &str) {
+        if self.stages.iter().any(|s| s == stage) {
+            panic!("Schedule already has stage: {}", stage);
+        }
+        self.stages.insert(index, String::from(stage));
+    }
+
+    /** Get the index of an existing stage, panics if it doesn't exist */
+    fn stage_index(&self, stage: &str) -> usize {
+        return match self.stages.iter().position(|s| s == stage) {
+            Some(index) => index,
+            None => panic!("Schedule doesn't have stage: {}", stage),
+        };
+    }
+}
+
+/** Lists the stages in the order they run, World::describe_schedule lists the systems of each stage too */
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.stages.join(" -> "));
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{PoisonError, RwLock},
+};
+
+use crate::{Component, ComponentRef, ComponentStorage, ComponentTicks};
+
+/** Stores components of type T packed together, indexed by entity */
+pub struct SparseSet<T> {
+    // Maps entity ids to indices in the dense arrays
+    sparse: Vec<Option<usize>>,
+    // The components
+    pub(crate) dense: Vec<RwLock<T>>,
+    // The change ticks of the components
+    pub(crate) ticks: Vec<ComponentTicks>,
+    // The entity owning each component in the dense array
+    entities: Vec<usize>,
+}
+
+impl<T: 'static + Component> Default for SparseSet<T> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl<T: 'static + Component> SparseSet<T> {
+    /** Construct an empty sparse set */
+    pub fn new() -> Self {
+        return Self {
+            sparse: vec![],
+            dense: vec![],
+            ticks: vec![],
+            entities: vec![],
+        };
+    }
+
+    /** Get the component cell of the entity */
+    pub fn get(&self, entity: usize) -> Option<&RwLock<T>> {
+        let index = (*self.sparse.get(entity)?)?;
+        return Some(&self.dense[index]);
+    }
+
+    /** Get the change ticks of the component of the entity */
+    pub fn get_ticks(&self, entity: usize) -> Option<&ComponentTicks> {
+        let index = (*self.sparse.get(entity)?)?;
+        return Some(&self.ticks[index]);
+    }
+
+    /** Get the entities that have a component in the set */
+    pub fn entities(&self) -> &[usize] {
+        return &self.entities;
+    }
+
+    /** Insert the component for the entity at the tick, replacing the existing one */
+    pub fn insert(&mut self, entity: usize, component: T, change_tick: u64) {
+        // Replace the component if the entity already has one
+        if let Some(Some(index)) = self.sparse.get(entity) {
+            self.dense[*index] = RwLock::new(component);
+            self.ticks[*index].set_changed(change_tick);
+            return;
+        }
+        // Grow the sparse array to fit the entity
+        if self.sparse.len() <= entity {
+            self.sparse.resize(entity + 1, None);
+        }
+        self.sparse[entity] = Some(self.dense.len());
+        self.dense.push(RwLock::new(component));
+        self.ticks.push(ComponentTicks::new(change_tick));
+        self.entities.push(entity);
+    }
+
+    /** Remove the component of the entity */
+    pub fn remove(&mut self, entity: usize) -> Option<T> {
+        let index = self.sparse.get_mut(entity)?.take()?;
+        // Move the last component into the removed slot
+        let component = self
+            .dense
+            .swap_remove(index)
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.ticks.swap_remove(index);
+        self.entities.swap_remove(index);
+        if let Some(moved) = self.entities.get(index) {
+            self.sparse[*moved] = Some(index);
+        }
+        return Some(component);
+    }
+}
+
+impl<T: 'static + Component> ComponentStorage for SparseSet<T> {
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        return self;
+    }
+
+    fn component_type(&self) -> TypeId {
+        return TypeId::of::<T>();
+    }
+
+    fn component_name(&self) -> &'static str {
+        return std::any::type_name::<T>();
+    }
+
+    fn contains(&self, entity: usize) -> bool {
+        return self.get(entity).is_some();
+    }
+
+    fn insert_boxed(&mut self, entity: usize, component: Box<dyn Component>, change_tick: u64) {
+        let component = match component.into_any().downcast::<T>() {
+            Ok(c) => c,
+            Err(_) => panic!(
+                "Component inserted into sparse set of the wrong type: {}",
+                std::any::type_name::<T>()
+            ),
+        };
+        self.insert(entity, *component, change_tick);
+    }
+
+    fn remove_boxed(&mut self, entity: usize) -> Option<Box<dyn Component>> {
+        return match self.remove(entity) {
+            Some(c) => Some(Box::new(c)),
+            None => None,
+        };
+    }
+
+    fn try_get_dyn(&self, entity: usize) -> Option<ComponentRef<'_>> {
+        return match self.get(entity)?.try_read() {
+            Ok(c) => Some(ComponentRef::new(c)),
+            Err(_) => None,
+        };
+    }
+}
+
+/** Stores the sparse sets of a scene */
+pub(crate) struct SparseSets {
+    // The sparse sets, in the order they were created
+    sets: Vec<Box<dyn ComponentStorage>>,
+    // Maps component types to the index of their sparse set
+    index: HashMap<TypeId, usize>,
+}
+
+impl SparseSets {
+    /** Construct an empty set of sparse sets */
+    pub(crate) fn new() -> Self {
+        return Self {
+            sets: vec![],
+            index: HashMap::new(),
+        };
+    }
+
+    /** Get the sparse set storing components of type T */
+    pub(crate) fn get<T: 'static + Component>(&self) -> Option<&SparseSet<T>> {
+        let index = self.index.get(&TypeId::of::<T>())?;
+        return self.sets[*index].as_any().downcast_ref::<SparseSet<T>>();
+    }
+
+    /** Get the sparse set storing components of type T mutably */
+    pub(crate) fn get_mut<T: 'static + Component>(&mut self) -> Option<&mut SparseSet<T>> {
+        let index = self.index.get(&TypeId::of::<T>())?;
+        return self.sets[*index]
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>();
+    }
+
+    /** Get the sparse set storing components of type T, creating it if needed */
+    pub(crate) fn get_or_create<T: 'static + Component>(&mut self) -> &mut SparseSet<T> {
+        if !self.index.contains_key(&TypeId::of::<T>()) {
+            self.sets.push(Box::new(SparseSet::<T>::new()));
+            self.index.insert(TypeId::of::<T>(), self.sets.len() - 1);
+        }
+        return self.get_mut::<T>().unwrap();
+    }
+
+    /** Get all sparse sets */
+    pub(crate) fn iter(&self) -> std::slice::Iter<'_, Box<dyn ComponentStorage>> {
+        return self.sets.iter();
+    }
+
+    /** Get all sparse sets mutably */
+    pub(crate) fn iter_mut(&mut self) -> std::slice::IterMut<'_, Box<dyn ComponentStorage>> {
+        return self.sets.iter_mut();
+    }
+
+    /** Insert a boxed component for the entity, creating the sparse set if needed */
+    pub(crate) fn insert_boxed(
+        &mut self,
+        entity: usize,
+        component: Box<dyn Component>,
+        change_tick: u64,
+    ) {
+        let component_type = component.as_any().type_id();
+        let index = match self.index.get(&component_type) {
+            Some(index) => *index,
+            None => {
+                self.sets.push(component.create_sparse_set());
+                self.index.insert(component_type, self.sets.len() - 1);
+                self.sets.len() - 1
+            }
+        };
+        self.sets[index].insert_boxed(entity, component, change_tick);
+    }
+}
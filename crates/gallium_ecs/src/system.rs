@@ -1,17 +1,179 @@
-use crate::{Scene, World};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-/** To be inherited by each ecs system */
+use crate::{Condition, Scene, SystemAccess, SystemOrder, World};
+
+/** To be inherited by each ecs system, systems are Send + Sync so they can run in parallel */
 #[typetag::serde(tag = "system")]
-pub trait System {
-    /** Tick the system */
-    fn tick(&self, _scene: &mut Scene, _world: &mut World) {}
+pub trait System: Send + Sync {
+    /** Tick the system, changes to the fields of the system are saved with the scene */
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {}
     /** Handle game event */
     fn on_event(
-        &self,
+        &mut self,
         _scene: &mut Scene,
         _world: &mut World,
         _tag: &str,
         _data: &dyn std::any::Any,
     ) {
     }
+    /**
+     * Declare the components and resources the system accesses.
+     * Systems declaring their access are ticked through tick_parallel instead of tick
+     */
+    fn access(&self) -> Option<SystemAccess> {
+        return None;
+    }
+    /**
+     * Tick the system alongside the neighbouring systems whose declared access doesn't conflict.
+     * Has to be implemented by systems declaring their access, panics otherwise
+     */
+    fn tick_parallel(&mut self, _scene: &Scene, _world: &World) {
+        panic!(
+            "System {} declares its access but doesn't implement tick_parallel",
+            self.typetag_name()
+        );
+    }
+    /** Get the label other systems refer to the system by if none is set */
+    fn default_label(&self) -> &str {
+        return self.typetag_name();
+    }
+}
+
+/** A system stored in a scene along with its bookkeeping */
+pub(crate) struct SystemEntry {
+    // The system
+    pub(crate) system: Box<dyn System>,
+    // The label and ordering constraints of the system
+    pub(crate) order: SystemOrder,
+    // The change tick the system last ran at
+    pub(crate) last_run: u64,
+    // Identifies the system while it runs
+    pub(crate) id: usize,
+    // The declared access of the system, none if it runs on its own
+    pub(crate) access: Option<SystemAccess>,
+    // Decides whether the system ticks, none if it always does
+    pub(crate) condition: Option<Box<dyn Condition>>,
+}
+
+impl SystemEntry {
+    /** Construct an entry for a system that hasn't run yet */
+    pub(crate) fn new(
+        system: Box<dyn System>,
+        order: SystemOrder,
+        condition: Option<Box<dyn Condition>>,
+    ) -> Self {
+        return Self {
+            access: system.access(),
+            system,
+            order,
+            last_run: 0,
+            id: next_system_id(),
+            condition,
+        };
+    }
+
+    /** Get the label of the system, the default label of the system if no label is set */
+    pub(crate) fn label(&self) -> &str {
+        return match self.order.get_label() {
+            Some(label) => label,
+            None => self.system.default_label(),
+        };
+    }
+
+    /** Run the closure as the system, so the scene and world know what the system has seen */
+    pub(crate) fn run_as<R, F: FnOnce(&mut dyn System) -> R>(&mut self, run: F) -> R {
+        let system = &mut *self.system;
+        return with_running_system(self.id, self.last_run, || run(system));
+    }
+
+    /** Evaluate the run condition as the system, systems without a condition always run */
+    pub(crate) fn should_run(&mut self, scene: &Scene, world: &World) -> bool {
+        let (id, last_run) = (self.id, self.last_run);
+        return match self.condition.as_mut() {
+            Some(condition) => {
+                with_running_system(id, last_run, || condition.evaluate(scene, world))
+            }
+            None => true,
+        };
+    }
+}
+
+thread_local! {
+    // The id of the system running on this thread and the change tick it last ran at
+    static RUNNING_SYSTEM: Cell<Option<(usize, u64)>> = const { Cell::new(None) };
+}
+
+/** Run the closure with the system as the system running on this thread */
+fn with_running_system<R, F: FnOnce() -> R>(id: usize, last_run: u64, run: F) -> R {
+    let previous = RUNNING_SYSTEM.with(|s| s.replace(Some((id, last_run))));
+    let result = run();
+    RUNNING_SYSTEM.with(|s| s.set(previous));
+    return result;
+}
+
+/** Get the id of the system running on this thread and the change tick it last ran at */
+pub(crate) fn running_system() -> Option<(usize, u64)> {
+    return RUNNING_SYSTEM.with(|s| s.get());
+}
+
+impl Serialize for SystemEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Systems without ordering or condition are stored on their own
+        if self.order.is_empty() && self.condition.is_none() {
+            return self.system.serialize(serializer);
+        }
+        return OrderedSystem {
+            system: &*self.system,
+            order: &self.order,
+            run_if: self.condition.as_deref(),
+        }
+        .serialize(serializer);
+    }
+}
+
+impl<'de> Deserialize<'de> for SystemEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        return match SerializedSystem::deserialize(deserializer)? {
+            SerializedSystem::Ordered {
+                system,
+                order,
+                run_if,
+            } => Ok(Self::new(system, order, run_if)),
+            SerializedSystem::System(system) => Ok(Self::new(system, SystemOrder::new(), None)),
+        };
+    }
+}
+
+/** A system stored along with its ordering and run condition */
+#[derive(Serialize)]
+struct OrderedSystem<'a> {
+    system: &'a dyn System,
+    #[serde(flatten)]
+    order: &'a SystemOrder,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_if: Option<&'a dyn Condition>,
+}
+
+/** The serialized forms of a system entry */
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SerializedSystem {
+    Ordered {
+        system: Box<dyn System>,
+        #[serde(flatten)]
+        order: SystemOrder,
+        #[serde(default)]
+        run_if: Option<Box<dyn Condition>>,
+    },
+    System(Box<dyn System>),
+}
+
+/** Get a new id to identify a system with */
+fn next_system_id() -> usize {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    return NEXT_ID.fetch_add(1, Ordering::Relaxed);
 }
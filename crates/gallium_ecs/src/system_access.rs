@@ -0,0 +1,106 @@
+use std::any::TypeId;
+
+use crate::{Component, Events};
+
+/**
+ * The component types and resources a system reads and writes.
+ * Systems whose access doesn't conflict can run in parallel
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemAccess {
+    // The component types the system reads, with their names
+    reads: Vec<(TypeId, &'static str)>,
+    // The component types the system writes, with their names
+    writes: Vec<(TypeId, &'static str)>,
+    // The resources the system reads, with their names
+    resource_reads: Vec<(TypeId, &'static str)>,
+    // The resources the system writes, with their names
+    resource_writes: Vec<(TypeId, &'static str)>,
+}
+
+impl SystemAccess {
+    /** Construct an access that touches nothing */
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /** Read the components of type T */
+    pub fn read<T: 'static + Component>(mut self) -> Self {
+        self.reads.push(type_of::<T>());
+        return self;
+    }
+
+    /** Write the components of type T, including adding and removing them through commands */
+    pub fn write<T: 'static + Component>(mut self) -> Self {
+        self.writes.push(type_of::<T>());
+        return self;
+    }
+
+    /** Read the resource of type T through World::read_resource */
+    pub fn read_resource<T: 'static>(mut self) -> Self {
+        self.resource_reads.push(type_of::<T>());
+        return self;
+    }
+
+    /** Write the resource of type T through World::get_resource */
+    pub fn write_resource<T: 'static>(mut self) -> Self {
+        self.resource_writes.push(type_of::<T>());
+        return self;
+    }
+
+    /** Send or read the events of type T, readers advance their cursor so both write the channel */
+    pub fn events<T: 'static>(self) -> Self {
+        return self.write_resource::<Events<T>>();
+    }
+
+    /** Check if the systems can run at the same time, neither writes what the other accesses */
+    pub fn is_compatible(&self, other: &SystemAccess) -> bool {
+        return self.conflict(other).is_none();
+    }
+
+    /** Get the name of a component type or resource one writes while the other accesses it */
+    pub fn conflict(&self, other: &SystemAccess) -> Option<&'static str> {
+        return conflict(&self.writes, &other.reads, &other.writes)
+            .or_else(|| conflict(&other.writes, &self.reads, &self.writes))
+            .or_else(|| {
+                conflict(
+                    &self.resource_writes,
+                    &other.resource_reads,
+                    &other.resource_writes,
+                )
+            })
+            .or_else(|| {
+                conflict(
+                    &other.resource_writes,
+                    &self.resource_reads,
+                    &self.resource_writes,
+                )
+            });
+    }
+
+    /** Add the access of the other system */
+    pub fn extend(&mut self, other: SystemAccess) {
+        self.reads.extend(other.reads);
+        self.writes.extend(other.writes);
+        self.resource_reads.extend(other.resource_reads);
+        self.resource_writes.extend(other.resource_writes);
+    }
+}
+
+/** Get the type-id and name of type T */
+fn type_of<T: 'static>() -> (TypeId, &'static str) {
+    return (TypeId::of::<T>(), std::any::type_name::<T>());
+}
+
+/** Find a written type that is also read or written elsewhere */
+fn conflict(
+    writes: &[(TypeId, &'static str)],
+    reads: &[(TypeId, &'static str)],
+    other_writes: &[(TypeId, &'static str)],
+) -> Option<&'static str> {
+    let accessed = |t: &TypeId| reads.iter().chain(other_writes).any(|(o, _)| o == t);
+    return writes
+        .iter()
+        .find(|(t, _)| accessed(t))
+        .map(|(_, name)| *name);
+}
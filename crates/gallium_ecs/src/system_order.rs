@@ -0,0 +1,392 @@
+use serde::{
+    de::{MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{cmp::Reverse, collections::BinaryHeap, fmt};
+
+use crate::SystemEntry;
+
+/** The label of a system and the labels of the systems it has to run before or after */
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemOrder {
+    // The label of the system, defaults to the name of the system type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    // The labels of the systems this system runs before
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    before: Vec<String>,
+    // The labels of the systems this system runs after
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    after: Vec<String>,
+}
+
+impl SystemOrder {
+    /** Construct an order without constraints */
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /** Set the label other systems refer to the system by */
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(String::from(label));
+        return self;
+    }
+
+    /** Run the system before the systems with the label */
+    pub fn before(mut self, label: &str) -> Self {
+        self.before.push(String::from(label));
+        return self;
+    }
+
+    /** Run the system after the systems with the label */
+    pub fn after(mut self, label: &str) -> Self {
+        self.after.push(String::from(label));
+        return self;
+    }
+
+    /** Check if the order has no label and no constraints */
+    pub fn is_empty(&self) -> bool {
+        return self == &Self::default();
+    }
+
+    /** Get the label of the system, if set */
+    pub fn get_label(&self) -> Option<&str> {
+        return self.label.as_deref();
+    }
+}
+
+/** The tag and system index and the label of each system, with the systems each one runs before */
+type SystemGraph<'a> = (Vec<(usize, usize)>, Vec<&'a str>, Vec<Vec<usize>>);
+
+/** The systems of a scene grouped by tag, in the order the tags were added */
+#[derive(Default)]
+pub(crate) struct SystemTags {
+    // The tags with their systems
+    tags: Vec<(String, Vec<SystemEntry>)>,
+    // The tag and system index of every system, in the order they run
+    order: Vec<(usize, usize)>,
+    // The tag and system index of every system that has to run before another one, with the other one
+    constraints: Vec<((usize, usize), (usize, usize))>,
+    // The version of the schedule the order was checked against, none if the order changed since
+    checked_schedule: Option<u64>,
+}
+
+impl SystemTags {
+    /** Construct an empty set of tags */
+    pub(crate) fn new() -> Self {
+        return Self::default();
+    }
+
+    /** Check if a tag has been added */
+    pub(crate) fn contains(&self, tag: &str) -> bool {
+        return self.tags.iter().any(|(t, _)| t == tag);
+    }
+
+    /** Add a system to the tag, creating the tag if needed. The order has to be updated afterwards */
+    pub(crate) fn push(&mut self, tag: &str, entry: SystemEntry) {
+        match self.tags.iter_mut().find(|(t, _)| t == tag) {
+            Some((_, entries)) => entries.push(entry),
+            None => self.tags.push((String::from(tag), vec![entry])),
+        }
+    }
+
+    /** Add the systems of other tags, keeping their tag order. The order has to be updated afterwards */
+    pub(crate) fn append(&mut self, other: SystemTags) {
+        for (tag, entries) in other.tags {
+            for entry in entries {
+                self.push(&tag, entry);
+            }
+        }
+    }
+
+    /**
+     * Add the systems of other tags and update the order.
+     * Returns an error if the order contains a cycle or refers to a label no system has, leaving the tags as they were
+     */
+    pub(crate) fn try_append(&mut self, other: SystemTags) -> Result<(), String> {
+        let lengths: Vec<usize> = self.tags.iter().map(|(_, entries)| entries.len()).collect();
+        self.append(other);
+        if let Err(e) = self.update_order() {
+            // Remove the added systems and tags, the order wasn't changed
+            self.tags.truncate(lengths.len());
+            for ((_, entries), length) in self.tags.iter_mut().zip(lengths) {
+                entries.truncate(length);
+            }
+            return Err(e);
+        }
+        return Ok(());
+    }
+
+    /** Add a system to the tag and update the order, leaving the tags as they were if the order is invalid */
+    pub(crate) fn try_push(&mut self, tag: &str, entry: SystemEntry) -> Result<(), String> {
+        let mut other = SystemTags::new();
+        other.push(tag, entry);
+        return self.try_append(other);
+    }
+
+    /** Get the names of the tags */
+    pub(crate) fn tags(&self) -> impl Iterator<Item = &str> {
+        return self.tags.iter().map(|(tag, _)| tag.as_str());
+    }
+
+    /** Get all systems */
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &SystemEntry> {
+        return self.tags.iter().flat_map(|(_, entries)| entries.iter());
+    }
+
+    /** Get the tag and system index of the systems in the order they run, only including the tag if provided */
+    pub(crate) fn ordered(&self, tag: Option<&str>) -> Vec<(usize, usize)> {
+        let tag_index = match tag {
+            Some(tag) => self.tags.iter().position(|(t, _)| t == tag),
+            None => None,
+        };
+        return self
+            .order
+            .iter()
+            .copied()
+            .filter(|(t, _)| tag.is_none() || Some(*t) == tag_index)
+            .collect();
+    }
+
+    /** Get a system by its tag and system index */
+    pub(crate) fn get(&self, tag: usize, index: usize) -> &SystemEntry {
+        return &self.tags[tag].1[index];
+    }
+
+    /** Get a mutable system by its tag and system index */
+    pub(crate) fn get_mut(&mut self, tag: usize, index: usize) -> &mut SystemEntry {
+        return &mut self.tags[tag].1[index];
+    }
+
+    /** Get several systems mutably at once by their tag and system index */
+    pub(crate) fn get_many_mut(&mut self, systems: &[(usize, usize)]) -> Vec<&mut SystemEntry> {
+        return self
+            .tags
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(t, (_, entries))| {
+                entries
+                    .iter_mut()
+                    .enumerate()
+                    .map(move |(i, e)| ((t, i), e))
+            })
+            .filter(|(system, _)| systems.contains(system))
+            .map(|(_, entry)| entry)
+            .collect();
+    }
+
+    /**
+     * Sort the systems so they run after the systems they have to run after, across all tags.
+     * Systems without constraints between them keep the order they were added in.
+     * Returns an error describing the cycle if the constraints contradict each other,
+     * or naming the label if a system has to run before or after a label no system has
+     */
+    pub(crate) fn update_order(&mut self) -> Result<(), String> {
+        let (nodes, labels, edges) = self.graph();
+        for entry in self.entries() {
+            let constraints = entry.order.before.iter().chain(entry.order.after.iter());
+            for label in constraints {
+                if !labels.contains(&label.as_str()) {
+                    return Err(format!(
+                        "System {} is ordered against label {}, but no system has that label",
+                        entry.label(),
+                        label
+                    ));
+                }
+            }
+        }
+        let mut in_degree = vec![0; nodes.len()];
+        for to in edges.iter().flatten() {
+            in_degree[*to] += 1;
+        }
+
+        // Run the first added system whose constraints are met
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..nodes.len())
+            .filter(|n| in_degree[*n] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = vec![];
+        while let Some(Reverse(node)) = ready.pop() {
+            order.push(nodes[node]);
+            for to in edges[node].iter() {
+                in_degree[*to] -= 1;
+                if in_degree[*to] == 0 {
+                    ready.push(Reverse(*to));
+                }
+            }
+        }
+
+        // Every system that couldn't be ordered has a constraint left from another one
+        if order.len() < nodes.len() {
+            let mut path = vec![(0..nodes.len()).find(|n| in_degree[*n] > 0).unwrap()];
+            loop {
+                let node = *path.last().unwrap();
+                let previous = (0..nodes.len())
+                    .find(|n| in_degree[*n] > 0 && edges[*n].contains(&node))
+                    .unwrap();
+                if let Some(start) = path.iter().position(|n| *n == previous) {
+                    // Describe the cycle in the order the systems would have to run
+                    let mut cycle = vec![labels[previous]];
+                    cycle.extend(path[start + 1..].iter().rev().map(|n| labels[*n]));
+                    cycle.push(labels[previous]);
+                    return Err(format!(
+                        "System order contains a cycle: {}",
+                        cycle.join(" -> ")
+                    ));
+                }
+                path.push(previous);
+            }
+        }
+        self.order = order;
+        self.constraints = edges
+            .iter()
+            .enumerate()
+            .flat_map(|(from, to_list)| to_list.iter().map(move |to| (from, *to)))
+            .map(|(from, to)| (nodes[from], nodes[to]))
+            .collect();
+        self.checked_schedule = None;
+        return Ok(());
+    }
+
+    /**
+     * Group the systems with the tag into batches in the order they run, each batch runs at once.
+     * Systems share a batch only if they all declare access that doesn't conflict and don't constrain each other
+     */
+    pub(crate) fn batches(&self, tag: &str) -> Vec<Vec<(usize, usize)>> {
+        let mut batches: Vec<Vec<(usize, usize)>> = vec![];
+        for system in self.ordered(Some(tag)) {
+            let joins = match batches.last() {
+                Some(batch) => batch.iter().all(|other| self.can_run_with(system, *other)),
+                None => false,
+            };
+            if joins {
+                batches.last_mut().unwrap().push(system);
+            } else {
+                batches.push(vec![system]);
+            }
+        }
+        return batches;
+    }
+
+    /** Check the order against a version of the schedule, unless it was checked against the version since it last changed */
+    pub(crate) fn check_schedule(&mut self, stages: &[String], version: u64) -> Result<(), String> {
+        if self.checked_schedule == Some(version) {
+            return Ok(());
+        }
+        self.check_stages(stages)?;
+        self.checked_schedule = Some(version);
+        return Ok(());
+    }
+
+    /** Check that no system has to run before a system in an earlier stage, the stages are tags */
+    fn check_stages(&self, stages: &[String]) -> Result<(), String> {
+        let (nodes, labels, edges) = self.graph();
+        // Find the stage of every system, systems outside of the stages don't run
+        let stage_of = |node: usize| {
+            let tag = &self.tags[nodes[node].0].0;
+            return stages.iter().position(|s| s == tag);
+        };
+        for (from, to_list) in edges.iter().enumerate() {
+            for to in to_list.iter() {
+                if let (Some(from_stage), Some(to_stage)) = (stage_of(from), stage_of(*to)) {
+                    if from_stage > to_stage {
+                        return Err(format!(
+                            "System {} in stage {} has to run before system {} in the earlier stage {}",
+                            labels[from], stages[from_stage], labels[*to], stages[to_stage]
+                        ));
+                    }
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    /** Get the labels of the systems with the tag, in the order they run */
+    pub(crate) fn labels(&self, tag: &str) -> Vec<&str> {
+        return self
+            .ordered(Some(tag))
+            .into_iter()
+            .map(|(t, i)| self.tags[t].1[i].label())
+            .collect();
+    }
+
+    /** Check if two systems can run at the same time */
+    fn can_run_with(&self, system: (usize, usize), other: (usize, usize)) -> bool {
+        let access = &self.get(system.0, system.1).access;
+        let other_access = &self.get(other.0, other.1).access;
+        return match (access, other_access) {
+            (Some(access), Some(other_access)) => {
+                access.is_compatible(other_access)
+                    && !self.constraints.contains(&(system, other))
+                    && !self.constraints.contains(&(other, system))
+            }
+            _ => false,
+        };
+    }
+
+    /**
+     * Get the tag and system index and the label of the systems in the order they were added,
+     * with an edge from every system to the systems that have to run after it
+     */
+    fn graph(&self) -> SystemGraph<'_> {
+        let mut nodes = vec![];
+        let mut entries = vec![];
+        for (t, (_, tag_entries)) in self.tags.iter().enumerate() {
+            for (i, entry) in tag_entries.iter().enumerate() {
+                nodes.push((t, i));
+                entries.push(entry);
+            }
+        }
+        let labels: Vec<&str> = entries.iter().map(|e| e.label()).collect();
+
+        let mut edges = vec![vec![]; nodes.len()];
+        for (from, entry) in entries.iter().enumerate() {
+            for (to, label) in labels.iter().enumerate() {
+                let before = entry.order.before.iter().any(|l| l == label);
+                let after = entries[to].order.after.iter().any(|l| l == labels[from]);
+                if from != to && (before || after) {
+                    edges[from].push(to);
+                }
+            }
+        }
+        return (nodes, labels, edges);
+    }
+}
+
+impl Serialize for SystemTags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.tags.len()))?;
+        for (tag, entries) in self.tags.iter() {
+            map.serialize_entry(tag, entries)?;
+        }
+        return map.end();
+    }
+}
+
+impl<'de> Deserialize<'de> for SystemTags {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        return deserializer.deserialize_map(SystemTagsVisitor);
+    }
+}
+
+/** Deserializes system tags, keeping the order of the tags */
+struct SystemTagsVisitor;
+
+impl<'de> Visitor<'de> for SystemTagsVisitor {
+    type Value = SystemTags;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "a map of tags to systems");
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut tags = SystemTags::new();
+        while let Some((tag, entries)) = map.next_entry::<String, Vec<SystemEntry>>()? {
+            for entry in entries {
+                tags.push(&tag, entry);
+            }
+        }
+        return Ok(tags);
+    }
+}
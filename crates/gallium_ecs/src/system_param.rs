@@ -0,0 +1,248 @@
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::{MutexGuard, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::{
+    Commands, Component, EventReader, EventWriter, QueryData, QueryFilter, QueryIter, Scene,
+    SystemAccess, World,
+};
+
+/** A parameter of a function system, fetched from the scene and world each time the system runs */
+pub trait SystemParam {
+    /** The parameter borrowing from the scene and world it was fetched from */
+    type Item<'a>;
+
+    /** Add what the parameter accesses, one entry per borrow so borrows within the parameter are checked too */
+    fn access(accesses: &mut Vec<SystemAccess>);
+    /** Fetch the parameter */
+    fn fetch<'a>(scene: &'a Scene, world: &'a World) -> Self::Item<'a>;
+}
+
+/** The parameter P borrowing from the scene and world for 'a */
+pub type SystemParamItem<'a, P> = <P as SystemParam>::Item<'a>;
+
+/** Query data that can be used in a Query parameter, independent of the lifetime it was written with */
+pub trait QueryParam {
+    /** The query data borrowing from the scene for 'a */
+    type Data<'a>: QueryData<'a>;
+
+    /** Add what the query data accesses, one entry per component */
+    fn access(accesses: &mut Vec<SystemAccess>);
+}
+
+impl<T: 'static + Component> QueryParam for &T {
+    type Data<'a> = &'a T;
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().read::<T>());
+    }
+}
+
+impl<T: 'static + Component> QueryParam for &mut T {
+    type Data<'a> = &'a mut T;
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().write::<T>());
+    }
+}
+
+impl<T: 'static + Component> QueryParam for Option<&T> {
+    type Data<'a> = Option<&'a T>;
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().read::<T>());
+    }
+}
+
+impl<T: 'static + Component> QueryParam for Option<&mut T> {
+    type Data<'a> = Option<&'a mut T>;
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().write::<T>());
+    }
+}
+
+impl QueryParam for () {
+    type Data<'a> = ();
+
+    fn access(_accesses: &mut Vec<SystemAccess>) {}
+}
+
+/** Implement query params for a tuple of query params */
+macro_rules! impl_query_param_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: QueryParam),*> QueryParam for ($($name,)*) {
+            type Data<'a> = ($($name::Data<'a>,)*);
+
+            fn access(accesses: &mut Vec<SystemAccess>) {
+                $($name::access(accesses);)*
+            }
+        }
+    };
+}
+
+impl_query_param_tuple!(A);
+impl_query_param_tuple!(A, B);
+impl_query_param_tuple!(A, B, C);
+impl_query_param_tuple!(A, B, C, D);
+impl_query_param_tuple!(A, B, C, D, E);
+impl_query_param_tuple!(A, B, C, D, E, F);
+impl_query_param_tuple!(A, B, C, D, E, F, G);
+impl_query_param_tuple!(A, B, C, D, E, F, G, H);
+
+/** Queries the active entities of the scene, only including entities matching the filter */
+pub struct Query<'a, Q, F = ()> {
+    // The scene being queried
+    scene: &'a Scene,
+    // The query data and filter are only used through their types
+    query: PhantomData<(Q, F)>,
+}
+
+impl<'a, Q: QueryParam, F: QueryFilter<'a>> Query<'a, Q, F> {
+    /** Iterate over the matching entities, yielding their ids with the fetched components */
+    pub fn iter(&self) -> QueryIter<'a, Q::Data<'a>, F> {
+        return self.scene.query_filtered::<Q::Data<'a>, F>();
+    }
+}
+
+impl<'a, Q: QueryParam, F: QueryFilter<'a>> IntoIterator for Query<'a, Q, F> {
+    type Item = (crate::EntityId, <Q::Data<'a> as QueryData<'a>>::Item);
+    type IntoIter = QueryIter<'a, Q::Data<'a>, F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.iter();
+    }
+}
+
+impl<Q: QueryParam, F: 'static + for<'a> QueryFilter<'a>> SystemParam for Query<'_, Q, F> {
+    type Item<'a> = Query<'a, Q::Data<'a>, F>;
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        Q::access(accesses);
+    }
+
+    fn fetch<'a>(scene: &'a Scene, _world: &'a World) -> Self::Item<'a> {
+        return Query {
+            scene,
+            query: PhantomData,
+        };
+    }
+}
+
+/** A shared borrow of the resource of type T */
+pub struct Res<'a, T>(RwLockReadGuard<'a, T>);
+
+impl<T> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        return &self.0;
+    }
+}
+
+impl<T: 'static> SystemParam for Res<'_, T> {
+    type Item<'a> = Res<'a, T>;
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().read_resource::<T>());
+    }
+
+    fn fetch<'a>(_scene: &'a Scene, world: &'a World) -> Self::Item<'a> {
+        return match world.read_resource::<T>() {
+            Some(resource) => Res(resource),
+            None => panic!(
+                "World doesn't have resource of type: {}",
+                std::any::type_name::<T>()
+            ),
+        };
+    }
+}
+
+/** A mutable borrow of the resource of type T */
+pub struct ResMut<'a, T>(RwLockWriteGuard<'a, T>);
+
+impl<T> Deref for ResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        return &self.0;
+    }
+}
+
+impl<T> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        return &mut self.0;
+    }
+}
+
+impl<T: 'static> SystemParam for ResMut<'_, T> {
+    type Item<'a> = ResMut<'a, T>;
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().write_resource::<T>());
+    }
+
+    fn fetch<'a>(_scene: &'a Scene, world: &'a World) -> Self::Item<'a> {
+        return match world.get_resource::<T>() {
+            Some(resource) => ResMut(resource),
+            None => panic!(
+                "World doesn't have resource of type: {}",
+                std::any::type_name::<T>()
+            ),
+        };
+    }
+}
+
+impl<T: 'static> SystemParam for EventReader<'_, T> {
+    type Item<'a> = EventReader<'a, T>;
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().events::<T>());
+    }
+
+    fn fetch<'a>(_scene: &'a Scene, world: &'a World) -> Self::Item<'a> {
+        return world.event_reader::<T>();
+    }
+}
+
+impl<T: 'static> SystemParam for EventWriter<'_, T> {
+    type Item<'a> = EventWriter<'a, T>;
+
+    fn access(accesses: &mut Vec<SystemAccess>) {
+        accesses.push(SystemAccess::new().events::<T>());
+    }
+
+    fn fetch<'a>(_scene: &'a Scene, world: &'a World) -> Self::Item<'a> {
+        return world.event_writer::<T>();
+    }
+}
+
+/** The command buffer of the running system, the queued structural changes are applied after the system */
+pub struct SystemCommands<'a>(MutexGuard<'a, Commands>);
+
+impl Deref for SystemCommands<'_> {
+    type Target = Commands;
+
+    fn deref(&self) -> &Commands {
+        return &self.0;
+    }
+}
+
+impl DerefMut for SystemCommands<'_> {
+    fn deref_mut(&mut self) -> &mut Commands {
+        return &mut self.0;
+    }
+}
+
+impl SystemParam for SystemCommands<'_> {
+    type Item<'a> = SystemCommands<'a>;
+
+    /** Queued commands are applied after the systems running in parallel, so they access nothing */
+    fn access(_accesses: &mut Vec<SystemAccess>) {}
+
+    fn fetch<'a>(scene: &'a Scene, _world: &'a World) -> Self::Item<'a> {
+        return SystemCommands(scene.commands());
+    }
+}
@@ -1,31 +1,129 @@
-use std::cell::{RefCell, RefMut};
+use std::{
+    sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError},
+    time::Duration,
+};
 
-use crate::{support::nameof, Scene};
+use crate::{
+    running_system, support::nameof, update_events, EventReader, EventWriter, Events,
+    FixedTimestep, LayerMode, Scene, Schedule, WorldScenes,
+};
 
 /** Represents a world that manages scenes */
 pub struct World {
-    // The current scene
-    scene: Option<Scene>,
+    // The scene stack with the current scene on top, and the named scenes
+    scenes: WorldScenes,
     // The world resources
     resources: Vec<Box<dyn WorldResource>>,
+    // The change tick, advanced each time a system runs
+    change_tick: u64,
+    // Update the event channels added to the world
+    event_updaters: Vec<fn(&World)>,
+    // The tags ticked by hand since the events were last updated
+    ticked_tags: Vec<String>,
+    // The stages run by run_schedule
+    schedule: Schedule,
+    // Advanced each time the schedule is set, scenes remember the version they were checked against
+    schedule_version: u64,
+    // The tags that run a fixed amount of steps per elapsed time
+    timesteps: Vec<(String, FixedTimestep)>,
 }
 
 impl World {
     /** Construct a new world */
     pub fn new() -> Self {
         return Self {
-            scene: None,
+            scenes: WorldScenes::new(),
             resources: vec![],
+            change_tick: 1,
+            event_updaters: vec![],
+            ticked_tags: vec![],
+            schedule: Schedule::new(),
+            schedule_version: 0,
+            timesteps: vec![],
         };
     }
 
-    /** Set the current scene */
+    /**
+     * Set the current scene, replacing every scene on the stack and running their exit systems.
+     * The startup systems of the scene run unless they already ran, which is remembered when the scene is saved
+     */
     pub fn set_scene(&mut self, scene: Scene) {
-        self.scene = Some(scene);
+        // Run the exit systems of the previous scenes from the top down
+        while !self.scenes.is_empty() {
+            self.pop_scene();
+        }
+        self.push_scene(scene, LayerMode::frozen());
+    }
+
+    /**
+     * Push a scene on top of the current scene, keeping the scenes below alive.
+     * The mode decides if the scenes below keep ticking and receiving events, the startup systems of the scene run
+     */
+    pub fn push_scene(&mut self, scene: Scene, mode: LayerMode) {
+        let id = self.scenes.push(scene, mode);
+        self.run_scene(id, |scene, world| scene.start(world));
+    }
+
+    /**
+     * Pop the scene on top, running its exit systems.
+     * Returns none if there are no scenes, or if a system of the scene popped it while running
+     */
+    pub fn pop_scene(&mut self) -> Option<Scene> {
+        let mut scene = self.scenes.pop()??;
+        scene.exit(self);
+        return Some(scene);
+    }
+
+    /** Get the amount of scenes on the stack */
+    pub fn scene_count(&self) -> usize {
+        return self.scenes.len();
+    }
+
+    /**
+     * Add a named scene, loaded alongside the scene stack until it is removed.
+     * Runs its startup systems, panics if the name is already used
+     */
+    pub fn add_scene(&mut self, name: &str, scene: Scene) {
+        let id = self.scenes.add_named(name, scene);
+        self.run_scene(id, |scene, world| scene.start(world));
+    }
+
+    /**
+     * Remove a named scene, running its exit systems.
+     * Returns none if there is no scene with the name, or if a system of the scene removed it while running
+     */
+    pub fn remove_scene(&mut self, name: &str) -> Option<Scene> {
+        let mut scene = self.scenes.remove_named(name)??;
+        scene.exit(self);
+        return Some(scene);
+    }
+
+    /** Check if the world has a scene with the name */
+    pub fn has_scene(&self, name: &str) -> bool {
+        return self.scenes.named_id(name).is_some();
+    }
+
+    /** Get the names of the named scenes, in the order they were added */
+    pub fn scene_names(&self) -> Vec<&str> {
+        return self.scenes.names();
     }
 
-    /** Insert a resource in the world */
-    pub fn insert_resource<T: 'static>(&mut self, resource: T) {
+    /**
+     * Get a named scene, letting systems look up entities in other scenes.
+     * Returns none if there is no scene with the name, or if its systems are running
+     */
+    pub fn scene(&self, name: &str) -> Option<&Scene> {
+        return self.scenes.get(self.scenes.named_id(name)?);
+    }
+
+    /** Get a named scene mutably, returns none if there is no scene with the name or if its systems are running */
+    pub fn scene_mut(&mut self, name: &str) -> Option<&mut Scene> {
+        let id = self.scenes.named_id(name)?;
+        return self.scenes.get_mut(id);
+    }
+
+    /** Insert a resource in the world, resources are Send + Sync so systems can run in parallel */
+    pub fn insert_resource<T: 'static + Send + Sync>(&mut self, resource: T) {
         // Make sure the resource doesn't already exist
         for r in &self.resources {
             if r.as_any().is::<T>() {
@@ -33,60 +131,402 @@ impl World {
             }
         }
         // Push the resource
-        self.resources.push(Box::new(RefCell::new(resource)));
+        self.resources.push(Box::new(RwLock::new(resource)));
     }
 
-    /** Get the resource from the world */
-    pub fn get_resource<T: 'static>(&self) -> Option<RefMut<T>> {
-        // Get the resource of the provided type
-        for r in &self.resources {
-            if let Some(r) = r.as_any().downcast_ref::<RefCell<T>>() {
-                return Some(r.borrow_mut());
+    /** Get the resource from the world, panics if it is already borrowed */
+    pub fn get_resource<T: 'static>(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        return match self.find_resource::<T>()?.try_write() {
+            Ok(r) => Some(r),
+            Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+            Err(TryLockError::WouldBlock) => panic!(
+                "Resource of type {} is already borrowed",
+                std::any::type_name::<T>()
+            ),
+        };
+    }
+
+    /** Get the resource from the world for reading only, panics if it is already mutably borrowed */
+    pub fn read_resource<T: 'static>(&self) -> Option<RwLockReadGuard<'_, T>> {
+        return match self.find_resource::<T>()?.try_read() {
+            Ok(r) => Some(r),
+            Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+            Err(TryLockError::WouldBlock) => panic!(
+                "Resource of type {} is already mutably borrowed",
+                std::any::type_name::<T>()
+            ),
+        };
+    }
+
+    /** Check if the world has the resource of provided type, without borrowing it */
+    pub fn has_resource<T: 'static>(&self) -> bool {
+        return self.find_resource::<T>().is_some();
+    }
+
+    /** Find the lock of the resource of provided type */
+    fn find_resource<T: 'static>(&self) -> Option<&RwLock<T>> {
+        return self
+            .resources
+            .iter()
+            .find_map(|r| r.as_any().downcast_ref::<RwLock<T>>());
+    }
+
+    /** Remove the resource of provided type from the world, returning it */
+    pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+        let index = self
+            .resources
+            .iter()
+            .position(|r| r.as_any().is::<RwLock<T>>())?;
+        let resource = self.resources.remove(index);
+        return match resource.into_any().downcast::<RwLock<T>>() {
+            Ok(r) => Some(r.into_inner().unwrap_or_else(PoisonError::into_inner)),
+            Err(_) => None,
+        };
+    }
+
+    /** Add a channel for events of type T */
+    pub fn add_event<T: 'static + Send + Sync>(&mut self) {
+        // Keep the existing channel
+        if self
+            .resources
+            .iter()
+            .any(|r| r.as_any().is::<RwLock<Events<T>>>())
+        {
+            return;
+        }
+        self.insert_resource(Events::<T>::new());
+        self.event_updaters.push(update_events::<T>);
+    }
+
+    /** Get a writer for the events of type T */
+    pub fn event_writer<T: 'static>(&self) -> EventWriter<'_, T> {
+        return EventWriter::new(self.get_events::<T>());
+    }
+
+    /** Get a reader for the events of type T, each system reads every event once */
+    pub fn event_reader<T: 'static>(&self) -> EventReader<'_, T> {
+        return EventReader::new(self.get_events::<T>(), running_system().map(|(id, _)| id));
+    }
+
+    /** Check if the running system has events of type T it hasn't read yet */
+    pub fn has_unread_events<T: 'static>(&self) -> bool {
+        return self
+            .get_events::<T>()
+            .has_unread(running_system().map(|(id, _)| id));
+    }
+
+    /** Send an event of type T */
+    pub fn send_event<T: 'static>(&self, event: T) {
+        self.get_events::<T>().send(event);
+    }
+
+    /**
+     * Update the event channels, dropping the events sent before the previous update.
+     * Called by run_schedule and update, and by tick_systems when a tag is ticked again since the last update
+     */
+    pub fn update_events(&mut self) {
+        for update in self.event_updaters.iter() {
+            update(self);
+        }
+        self.ticked_tags.clear();
+    }
+
+    /** Update the events before a tag is ticked by hand, if the tag was already ticked since the last update */
+    fn update_events_for(&mut self, tag: &str) {
+        if self.ticked_tags.iter().any(|t| t == tag) {
+            self.update_events();
+        }
+        self.ticked_tags.push(String::from(tag));
+    }
+
+    /** Get the channel for events of type T */
+    fn get_events<T: 'static>(&self) -> RwLockWriteGuard<'_, Events<T>> {
+        return match self.get_resource::<Events<T>>() {
+            Some(events) => events,
+            None => panic!(
+                "World doesn't have events of type: {}",
+                std::any::type_name::<T>()
+            ),
+        };
+    }
+
+    /** Get the current change tick */
+    pub fn change_tick(&self) -> u64 {
+        return self.change_tick;
+    }
+
+    /** Advance the change tick, returning the new tick. The tick is 64 bits so it never wraps around */
+    pub(crate) fn increment_change_tick(&mut self) -> u64 {
+        self.change_tick += 1;
+        return self.change_tick;
+    }
+
+    /**
+     * Set the stages run by run_schedule.
+     * Fails if a system of a scene in the world has to run before a system in an earlier stage, keeping the previous schedule.
+     * Scenes added later, or whose systems changed, are checked the next time the schedule runs
+     */
+    pub fn set_schedule(&mut self, schedule: Schedule) -> Result<(), String> {
+        let version = self.schedule_version + 1;
+        for id in self.scenes.ids() {
+            if let Some(scene) = self.scenes.get_mut(id) {
+                scene.check_schedule(schedule.stages(), version)?;
             }
         }
-        // Return none if the resource hasn't been found
-        return None;
+        self.schedule = schedule;
+        self.schedule_version = version;
+        return Ok(());
     }
 
-    /** Tick the systems in the current scene */
+    /** Get the stages run by run_schedule */
+    pub fn schedule(&self) -> &Schedule {
+        return &self.schedule;
+    }
+
+    /**
+     * Run the systems of the current scene stage by stage, then update the events.
+     * Every stage runs once, including the stages with a fixed timestep.
+     * Fails without running anything if a system has to run before a system in an earlier stage
+     */
+    pub fn run_schedule(&mut self) -> Result<(), String> {
+        return self.run_stages(None);
+    }
+
+    /**
+     * Run the schedule for a frame that took the elapsed time.
+     * Stages with a fixed timestep run as many steps as the elapsed time covers, the other stages run once.
+     * Fails without running anything if a system has to run before a system in an earlier stage
+     */
+    pub fn update(&mut self, elapsed: Duration) -> Result<(), String> {
+        return self.run_stages(Some(elapsed));
+    }
+
+    /** Run the stages of the schedule, advancing the fixed timesteps by the elapsed time if provided */
+    fn run_stages(&mut self, elapsed: Option<Duration>) -> Result<(), String> {
+        let stages = self.schedule.stages().to_vec();
+        if self.scenes.has_no_scenes() {
+            panic!("Scene not set in world.");
+        }
+        // Check the scenes added or changed since they were last checked against the schedule
+        for id in self.scenes.ids() {
+            if let Some(scene) = self.scenes.get_mut(id) {
+                scene.check_schedule(&stages, self.schedule_version)?;
+            }
+        }
+        // Run the stages, skipping the stages the ticked scenes have no systems for
+        for stage in stages.iter() {
+            if self.ticked_with(stage).is_empty() {
+                continue;
+            }
+            let steps = match (elapsed, self.get_timestep_mut(stage)) {
+                (Some(elapsed), Some(timestep)) => timestep.advance(elapsed),
+                _ => 1,
+            };
+            for _ in 0..steps {
+                self.tick_layers(stage);
+            }
+        }
+        self.update_events();
+        return Ok(());
+    }
+
+    /** Run the tag a fixed amount of steps per elapsed time, replacing its existing timestep */
+    pub fn set_fixed_timestep(&mut self, tag: &str, timestep: FixedTimestep) {
+        match self.get_timestep_mut(tag) {
+            Some(existing) => *existing = timestep,
+            None => self.timesteps.push((String::from(tag), timestep)),
+        }
+    }
+
+    /** Run the tag once per update again, returning its timestep */
+    pub fn remove_fixed_timestep(&mut self, tag: &str) -> Option<FixedTimestep> {
+        let index = self.timesteps.iter().position(|(t, _)| t == tag)?;
+        return Some(self.timesteps.remove(index).1);
+    }
+
+    /** Get the timestep of the tag, systems interpolate between steps with its alpha */
+    pub fn fixed_timestep(&self, tag: &str) -> Option<&FixedTimestep> {
+        return self
+            .timesteps
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map(|(_, timestep)| timestep);
+    }
+
+    /**
+     * Tick the systems with the tag as many steps as the elapsed time covers, returning the amount of steps.
+     * Panics if the tag has no fixed timestep
+     */
+    pub fn tick_fixed(&mut self, tag: &str, elapsed: Duration) -> u32 {
+        let steps = match self.get_timestep_mut(tag) {
+            Some(timestep) => timestep.advance(elapsed),
+            None => panic!("World doesn't have a fixed timestep for tag: {}", tag),
+        };
+        self.update_events_for(tag);
+        for _ in 0..steps {
+            self.tick_layers(tag);
+        }
+        return steps;
+    }
+
+    /** Get the timestep of the tag mutably */
+    fn get_timestep_mut(&mut self, tag: &str) -> Option<&mut FixedTimestep> {
+        return self
+            .timesteps
+            .iter_mut()
+            .find(|(t, _)| t == tag)
+            .map(|(_, timestep)| timestep);
+    }
+
+    /**
+     * Describe the stages of the schedule with the systems of the ticked scenes they run, in the order they run.
+     * Each line is a step of the stage, systems running in parallel share a step
+     */
+    pub fn describe_schedule(&self) -> String {
+        let mut description = String::new();
+        for stage in self.schedule.stages() {
+            description.push_str(stage);
+            if let Some(timestep) = self.fixed_timestep(stage) {
+                description.push_str(&format!(
+                    " (every {:?}, at most {} steps)",
+                    timestep.step(),
+                    timestep.max_steps()
+                ));
+            }
+            description.push_str(":\n");
+            let mut step = 0;
+            for id in self.ticked_with(stage) {
+                for batch in self.scenes.get(id).unwrap().system_batches(stage) {
+                    step += 1;
+                    description.push_str(&format!("  {}. {}\n", step, batch.join(" | ")));
+                }
+            }
+        }
+        return description;
+    }
+
+    /**
+     * Tick the systems in the current scene, then in the named scenes.
+     * The scenes below tick first if the scenes above them let them, from the bottom up.
+     * Ticking a tag again updates the events first, so events live until every tag ticked once more
+     */
     pub fn tick_systems(&mut self, tag: &str) {
-        // Take ownsership of the scene
-        let mut scene = self.scene.take().expect("Scene not set in world.");
-        // Tick the systems in the scene
-        scene.tick_systems(tag, self);
+        self.update_events_for(tag);
+        self.tick_layers(tag);
+    }
+
+    /** Tick the systems in the ticked scenes without updating the events */
+    fn tick_layers(&mut self, tag: &str) {
+        if self.scenes.has_no_scenes() {
+            panic!("Scene not set in world.");
+        }
+        let layers = self.ticked_with(tag);
+        if layers.is_empty() {
+            println!("Scene doesnt include system with secified tag: {}", tag);
+            return;
+        }
+        // Tick the systems in the scenes
+        for id in layers {
+            self.run_scene(id, |scene, world| scene.tick_systems(tag, world));
+        }
+    }
 
-        // Make sure the scene hasn't been changed
-        if self.scene.is_none() {
-            // Return ownsership of the scene
-            self.scene = Some(scene);
+    /**
+     * Tick the systems in the named scenes in the order of the names, panics if a scene doesn't exist.
+     * Ticking a tag again updates the events first, like tick_systems
+     */
+    pub fn tick_scenes(&mut self, tag: &str, names: &[&str]) {
+        let ids = self.named_ids(names);
+        self.update_events_for(tag);
+        for id in ids {
+            if self.scenes.get(id).is_some_and(|s| s.has_systems(tag)) {
+                self.run_scene(id, |scene, world| scene.tick_systems(tag, world));
+            }
         }
     }
 
-    /** Dispatch an event over the the systems in the current scene */
+    /**
+     * Dispatch an event over the the systems in the current scene, then in the named scenes.
+     * The scenes below receive it after if the scenes above them let them, from the top down.
+     * Kept for compatibility, typed events are sent with send_event and read with event_reader
+     */
     pub fn dispatch_event(&mut self, tag: &str, data: &dyn std::any::Any) {
-        // Take ownsership of the scene
-        let mut scene = self.scene.take().expect("Scene not set in world.");
-        // Tick the systems in the scene
-        scene.dispatch_event(tag, self, data);
+        if self.scenes.has_no_scenes() {
+            panic!("Scene not set in world.");
+        }
+        // Dispatch the event over the systems in the scenes
+        let mut layers = self.scenes.dispatched();
+        layers.extend(self.scenes.named());
+        for id in layers {
+            self.run_scene(id, |scene, world| scene.dispatch_event(tag, world, data));
+        }
+    }
 
-        // Make sure the scene hasn't been changed
-        if self.scene.is_none() {
-            // Return ownsership of the scene
-            self.scene = Some(scene);
+    /**
+     * Dispatch an event over the systems in the named scenes in the order of the names.
+     * Panics if a scene doesn't exist
+     */
+    pub fn dispatch_event_to(&mut self, names: &[&str], tag: &str, data: &dyn std::any::Any) {
+        for id in self.named_ids(names) {
+            self.run_scene(id, |scene, world| scene.dispatch_event(tag, world, data));
+        }
+    }
+
+    /** Get the ticked scenes, the scenes on the stack from the bottom up and then the named scenes */
+    fn ticked(&self) -> Vec<u64> {
+        let mut layers = self.scenes.ticked();
+        layers.extend(self.scenes.named());
+        return layers;
+    }
+
+    /** Get the ticked scenes with systems with the tag */
+    fn ticked_with(&self, tag: &str) -> Vec<u64> {
+        return self
+            .ticked()
+            .into_iter()
+            .filter(|id| self.scenes.get(*id).is_some_and(|s| s.has_systems(tag)))
+            .collect();
+    }
+
+    /** Get the layers of the named scenes, panics if a scene doesn't exist */
+    fn named_ids(&self, names: &[&str]) -> Vec<u64> {
+        return names
+            .iter()
+            .map(|name| match self.scenes.named_id(name) {
+                Some(id) => id,
+                None => panic!("World doesn't have scene named: {}", name),
+            })
+            .collect();
+    }
+
+    /** Run a function on the scene of a layer, taking ownership of the scene while it runs */
+    fn run_scene<F: FnOnce(&mut Scene, &mut World)>(&mut self, id: u64, run: F) {
+        // Take ownsership of the scene, skipping scenes removed by earlier systems
+        let mut scene = match self.scenes.take(id) {
+            Some(scene) => scene,
+            None => return,
+        };
+        run(&mut scene, self);
+
+        // Return ownsership of the scene, running its exit systems if a system removed it
+        if let Some(mut scene) = self.scenes.restore(id, scene) {
+            scene.exit(self);
         }
     }
 }
 
 /** Represents a world resource */
-pub trait WorldResource {
+pub trait WorldResource: Send + Sync {
     /** Return resource as any */
     fn as_any(&self) -> &dyn std::any::Any;
     /** Return resource as mutable any */
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+    /** Convert the boxed resource into a boxed any */
+    fn into_any(self: Box<Self>) -> Box<dyn std::any::Any>;
 }
 
 /** Implement world_resource for any type */
-impl<T: 'static> WorldResource for RefCell<T> {
+impl<T: 'static + Send + Sync> WorldResource for RwLock<T> {
     /** Return resource as any */
     fn as_any(&self) -> &dyn std::any::Any {
         return self;
@@ -96,4 +536,9 @@ impl<T: 'static> WorldResource for RefCell<T> {
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         return self;
     }
+
+    /** Convert the boxed resource into a boxed any */
+    fn into_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        return self;
+    }
 }
@@ -3,16 +3,42 @@
 /** Proc-macro attribute for components */
 #[proc_macro_attribute]
 pub fn component(
-    _metadata: proc_macro::TokenStream,
+    metadata: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
+    // Get the storage type and how entity ids are mapped
+    let mut storage = String::new();
+    let mut map_entities = String::new();
+    let mut map_entities_impl = String::new();
+    for argument in metadata.to_string().split(',') {
+        let argument = argument.split('=').map(|s| s.trim()).collect::<Vec<&str>>();
+        match argument[..] {
+            [""] => {}
+            ["storage", "\"table\""] => {}
+            ["storage", "\"sparse_set\""] => {
+                storage = String::from(
+                    "fn storage_type(&self) -> ::gallium_ecs::StorageType { return ::gallium_ecs::StorageType::SparseSet; }",
+                );
+            }
+            ["map_entities"] => {
+                map_entities = String::from(MAP_ENTITIES);
+                map_entities_impl = map_entity_fields(&input.to_string());
+            }
+            ["map_entities", "\"custom\""] => {
+                map_entities = String::from(MAP_ENTITIES);
+            }
+            _ => panic!("Unknown component argument: {}", argument.join(" = ")),
+        }
+    }
+
     // Get the name
     let p_input = input.to_string();
     let p_input = p_input
     .replace("(", " ")
     .replace(")", " ")
     .replace("{", " ")
-    .replace("}", " ");
+    .replace("}", " ")
+    .replace(";", " ");
     let p_input = p_input.split(' ').collect::<Vec<&str>>();
 
     let name_index = p_input
@@ -38,13 +64,89 @@ pub fn component(
             fn as_any_mut(&mut self) -> &mut dyn std::any::Any {{
                 return self;
             }}
+
+            {}
+
+            {}
         }}
+
+        {}
     "#,
-        input, name
+        input, name, storage, map_entities, map_entities_impl
     );
     return output.parse().unwrap();
 }
 
+/** Forwards the entity mapping of a component to its MapEntities implementation */
+const MAP_ENTITIES: &str =
+    "fn map_entities(&mut self, map: &::gallium_ecs::EntityMap) { ::gallium_ecs::MapEntities::map_entities(self, map); }";
+
+/** Implement MapEntities for a struct, mapping every field storing entity ids */
+fn map_entity_fields(input: &str) -> String {
+    let input = match syn::parse_derive_input(input) {
+        Ok(i) => i,
+        Err(e) => panic!("Failed to parse component: {}", e),
+    };
+    let fields = match input.body {
+        syn::Body::Struct(data) => data.fields().to_vec(),
+        syn::Body::Enum(_) => panic!(
+            "Entity fields can only be mapped for structs: {}",
+            input.ident
+        ),
+    };
+
+    // Map the fields storing entity ids
+    let mut map_fields = String::new();
+    for (index, field) in fields.iter().enumerate() {
+        if !stores_entity_ids(&field.ty) {
+            continue;
+        }
+        let name = match &field.ident {
+            Some(ident) => ident.to_string(),
+            None => index.to_string(),
+        };
+        map_fields.push_str(&format!(
+            "::gallium_ecs::MapEntities::map_entities(&mut self.{}, map);\n",
+            name
+        ));
+    }
+
+    return format!(
+        r#"
+        impl ::gallium_ecs::MapEntities for {} {{
+            #[allow(unused_variables)]
+            fn map_entities(&mut self, map: &::gallium_ecs::EntityMap) {{
+                {}
+            }}
+        }}
+    "#,
+        input.ident, map_fields
+    );
+}
+
+/**
+ * Check if a type is an EntityId, or an Option or Vec of them, matched on the last segment of its path.
+ * Aliased types aren't recognized, components storing them implement MapEntities themselves
+ */
+fn stores_entity_ids(ty: &syn::Ty) -> bool {
+    let segment = match ty {
+        syn::Ty::Path(None, path) => match path.segments.last() {
+            Some(segment) => segment,
+            None => return false,
+        },
+        syn::Ty::Paren(ty) => return stores_entity_ids(ty),
+        _ => return false,
+    };
+    return match (segment.ident.as_ref(), &segment.parameters) {
+        ("EntityId", parameters) => parameters.is_empty(),
+        ("Option", syn::PathParameters::AngleBracketed(data))
+        | ("Vec", syn::PathParameters::AngleBracketed(data)) => {
+            data.types.len() == 1 && stores_entity_ids(&data.types[0])
+        }
+        _ => false,
+    };
+}
+
 /** Proc-macro attribute for systems */
 #[proc_macro_attribute]
 pub fn system(
@@ -61,3 +163,67 @@ pub fn system(
     .parse()
     .unwrap();
 }
+
+/** Proc-macro attribute for system run conditions */
+#[proc_macro_attribute]
+pub fn condition(
+    _metadata: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    return format!(
+        r#"
+        #[typetag::serde]
+        {}
+    "#,
+        input
+    )
+    .parse()
+    .unwrap();
+}
+
+/** Derive macro for component bundles, every field has to be a component or a bundle */
+#[proc_macro_derive(Bundle)]
+pub fn bundle(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = match syn::parse_derive_input(&input.to_string()) {
+        Ok(i) => i,
+        Err(e) => panic!("Failed to parse bundle: {}", e),
+    };
+
+    // Get the fields
+    let fields = match input.body {
+        syn::Body::Struct(data) => data.fields().to_vec(),
+        syn::Body::Enum(_) => panic!("Bundle can only be derived for structs: {}", input.ident),
+    };
+    if !input.generics.lifetimes.is_empty() || !input.generics.ty_params.is_empty() {
+        panic!(
+            "Bundle can't be derived for generic structs: {}",
+            input.ident
+        );
+    }
+
+    // Move the components of each field into the list
+    let mut take_fields = String::new();
+    for (index, field) in fields.iter().enumerate() {
+        let name = match &field.ident {
+            Some(ident) => ident.to_string(),
+            None => index.to_string(),
+        };
+        take_fields.push_str(&format!(
+            "Bundle::take_components(self.{}, components);\n",
+            name
+        ));
+    }
+
+    return format!(
+        r#"
+        impl Bundle for {} {{
+            fn take_components(self, components: &mut Vec<Box<dyn Component>>) {{
+                {}
+            }}
+        }}
+    "#,
+        input.ident, take_fields
+    )
+    .parse()
+    .unwrap();
+}
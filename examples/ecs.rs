@@ -13,17 +13,9 @@ struct GreetSystem;
 #[gallium::system]
 impl System for GreetSystem {
     /** Gets run each time the system is ticked */
-    fn tick(&self, scene: &mut Scene, _world: &mut World) {
-        // Fetch the entities
-        let entities = scene
-            .get_entities()
-            .are_active()
-            .with_component::<NameComponent>();
-
-        // Loop over the entities
-        for entity in entities.iter() {
-            // Fetch the name component
-            let name_component = entity.get_component::<NameComponent>().unwrap();
+    fn tick(&mut self, scene: &mut Scene, _world: &mut World) {
+        // Loop over the active entities with a name component
+        for (_id, name_component) in scene.query::<&NameComponent>() {
             // Greet the name
             println!("Hello, {}!", name_component.name);
         }
@@ -49,7 +41,7 @@ pub fn create_scene() -> Scene {
     let scene = SceneBuilder::new()
         .with_entity(entity1)
         .with_entity(entity2)
-        .with_system("init", GreetSystem {})
+        .with_startup_system(GreetSystem {})
         .build();
 
     return scene;
@@ -62,9 +54,6 @@ fn main() {
     // Create the scene
     let scene = create_scene();
 
-    // Add scene to the world
+    // Add scene to the world, running its startup systems
     world.set_scene(scene);
-
-    // Tick the scenes init systems
-    world.tick_systems("init");
 }
@@ -12,17 +12,9 @@ struct GreetSystem;
 
 #[gallium::system]
 impl System for GreetSystem {
-    fn tick(&self, scene: &mut Scene, _world: &mut World) {
-        // Fetch the entities
-        let entities = scene
-            .get_entities()
-            .are_active()
-            .with_component::<NameComponent>();
-
-        // Loop over the entities
-        for entity in entities.iter() {
-            // Fetch the name component
-            let name_component = entity.get_component::<NameComponent>().unwrap();
+    fn tick(&mut self, scene: &mut Scene, _world: &mut World) {
+        // Loop over the active entities with a name component
+        for (_id, name_component) in scene.query::<&NameComponent>() {
             // Greet the name
             println!("Hello, {}!", name_component.name);
         }
@@ -48,7 +40,7 @@ pub fn create_scene() {
     let s_scene = SceneBuilder::new()
         .with_entity(entity1)
         .with_entity(entity2)
-        .with_system("init", GreetSystem {})
+        .with_startup_system(GreetSystem {})
         .build();
 
     // Serialize the scene
@@ -64,9 +56,6 @@ fn main() {
     // Import the scene
     let scene = Scene::import_ron("examples/test_scene.ron").unwrap();
 
-    // Add scene to the world
+    // Add scene to the world, running its startup systems
     world.set_scene(scene);
-
-    // Tick the scenes init systems
-    world.tick_systems("init");
 }
@@ -1,4 +1,4 @@
-use gallium::{Component, EntityBuilder, EntityList, SceneBuilder, System, World, serde::{Serialize, Deserialize}};
+use gallium::{Component, EntityBuilder, LayerMode, SceneBuilder, System, World, serde::{Serialize, Deserialize}};
 
 /** A name component for an entity */
 #[gallium::component]
@@ -10,18 +10,12 @@ pub struct TestSystem;
 
 #[gallium::system]
 impl System for TestSystem {
-    fn tick(&self, scene: &mut gallium::Scene, world: &mut World) {
-        // Get the entities with name components
-        let entities = scene.get_entities()
-        .are_active()
-        .with_component::<Name>();
-
-        // Loop over entities
-        for entity in &entities {
-            let name_component = entity.get_component::<Name>().unwrap();
+    fn tick(&mut self, scene: &mut gallium::Scene, world: &mut World) {
+        // Loop over the entities with name components
+        for (id, name_component) in scene.query::<&Name>() {
             let name = &name_component.0;
 
-            println!("Hello, {}:{}!", name, entity.id());
+            println!("Hello, {}:{}!", name, id);
         }
     }
 }
@@ -50,8 +44,13 @@ fn main() {
     // Tick the systems in scene_a
     world.tick_systems("test");
 
-    // Set the scene to the second scene
-    world.set_scene(scene_b);
+    // Push the second scene over the first scene, letting the first scene keep ticking
+    world.push_scene(scene_b, LayerMode::overlay());
+    // Tick the systems in scene_a and scene_b
+    world.tick_systems("test");
+
+    // Pop the second scene, the first scene is the current scene again
+    world.pop_scene();
     // Tick the systems in scene_a
     world.tick_systems("test");
 }
\ No newline at end of file
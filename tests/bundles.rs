@@ -0,0 +1,58 @@
+use gallium::*;
+
+#[gallium::component]
+struct Pos(i32);
+
+#[gallium::component]
+struct Vel(i32);
+
+#[gallium::component]
+struct Sprite(String);
+
+#[gallium::component]
+struct Frame(u32);
+
+#[derive(Bundle)]
+struct PlayerBundle {
+    pos: Pos,
+    vel: Vel,
+    // A nested bundle
+    sprite: (Sprite, Frame),
+}
+
+/** Construct a player bundle at the position */
+fn player(x: i32) -> PlayerBundle {
+    return PlayerBundle {
+        pos: Pos(x),
+        vel: Vel(1),
+        sprite: (Sprite(String::from("player")), Frame(0)),
+    };
+}
+
+#[test]
+fn builders_add_every_component_of_a_bundle() {
+    let mut scene = Scene::new();
+    let id = scene.add_entity(EntityBuilder::new().with_bundle(player(3)).build());
+    let found: Vec<(EntityId, i32, i32, String, u32)> = scene
+        .query::<(&Pos, &Vel, &Sprite, &Frame)>()
+        .map(|(id, (pos, vel, sprite, frame))| (id, pos.0, vel.0, sprite.0.clone(), frame.0))
+        .collect();
+    assert_eq!(found, vec![(id, 3, 1, String::from("player"), 0)]);
+}
+
+#[test]
+fn inserted_bundles_replace_existing_components() {
+    let mut scene = Scene::new();
+    let id = scene.add_entity(EntityBuilder::new().with(Pos(0)).with(Frame(9)).build());
+    assert!(scene.insert_bundle(id, (Pos(5), Vel(2))));
+
+    let entity = scene.get_entity(id).unwrap();
+    assert_eq!(entity.get_component::<Pos>().unwrap().0, 5);
+    assert_eq!(entity.get_component::<Vel>().unwrap().0, 2);
+    assert_eq!(entity.get_component::<Frame>().unwrap().0, 9);
+
+    let mut entity = EntityBuilder::new().with(Vel(7)).build();
+    entity.insert_bundle(player(1));
+    assert_eq!(entity.get_component::<Vel>().unwrap().0, 1);
+    assert_eq!(entity.get_component::<Sprite>().unwrap().0, "player");
+}
@@ -0,0 +1,75 @@
+use gallium::*;
+use std::sync::Mutex;
+
+#[gallium::component]
+struct Pos(i32);
+
+/** Moves the entities at the origin to one, only reading the others */
+#[derive(Serialize, Deserialize)]
+struct Mover;
+
+#[gallium::system]
+impl System for Mover {
+    fn tick(&mut self, scene: &mut Scene, _world: &mut World) {
+        for (_, mut pos) in scene.query::<&mut Pos>() {
+            if pos.0 == 0 {
+                pos.0 = 1;
+            }
+        }
+    }
+}
+
+/** The amount of added and changed positions seen by each run of the detector */
+static SEEN: Mutex<Vec<(usize, usize)>> = Mutex::new(vec![]);
+
+/** Records the positions added and changed since it last ran */
+#[derive(Serialize, Deserialize)]
+struct Detector;
+
+#[gallium::system]
+impl System for Detector {
+    fn tick(&mut self, scene: &mut Scene, _world: &mut World) {
+        let added = scene.query_filtered::<&Pos, Added<Pos>>().count();
+        let changed = scene.query_filtered::<&Pos, Changed<Pos>>().count();
+        SEEN.lock().unwrap().push((added, changed));
+    }
+}
+
+#[test]
+fn systems_see_what_changed_since_they_last_ran() {
+    let mut scene = SceneBuilder::new()
+        .with_entity(EntityBuilder::new().with(Pos(0)).build())
+        .with_entity(EntityBuilder::new().with(Pos(5)).build())
+        .with_system("update", Mover)
+        .with_system("update", Detector)
+        .build();
+    let mut world = World::new();
+
+    // Both entities are new, one of them is moved
+    scene.tick_systems("update", &mut world);
+    // Nothing was written to
+    scene.tick_systems("update", &mut world);
+    // Only the new entity is seen
+    scene.add_entity(EntityBuilder::new().with(Pos(7)).build());
+    scene.tick_systems("update", &mut world);
+    // Writing after the detector ran is seen on its next run, changing twice counts once
+    scene
+        .add_system(
+            "update",
+            FunctionSystem::named("reset", |query: Query<&mut Pos>| {
+                for (_, mut pos) in query {
+                    if pos.0 == 7 {
+                        pos.0 = 0;
+                    }
+                }
+            }),
+        )
+        .unwrap();
+    scene.tick_systems("update", &mut world);
+    scene.tick_systems("update", &mut world);
+
+    assert_eq!(
+        *SEEN.lock().unwrap(),
+        vec![(2, 2), (0, 0), (1, 1), (0, 0), (0, 1)]
+    );
+}
@@ -0,0 +1,86 @@
+use gallium::*;
+use std::sync::Mutex;
+
+#[gallium::component]
+struct Health(i32);
+
+#[gallium::component]
+struct Dead;
+
+/** The score kept in the world */
+struct Score(u32);
+
+/** The amount of entities the reaper saw before and after queuing its commands */
+static SEEN: Mutex<Vec<(usize, usize)>> = Mutex::new(vec![]);
+
+/** Marks the entities without health as dead, replacing them with new ones */
+#[derive(Serialize, Deserialize)]
+struct Reaper;
+
+#[gallium::system]
+impl System for Reaper {
+    fn tick(&mut self, scene: &mut Scene, _world: &mut World) {
+        let before = scene.query::<&Health>().count();
+        for (id, health) in scene.query::<&Health>() {
+            if health.0 <= 0 {
+                let mut commands = scene.commands();
+                commands.insert_component(id, Dead);
+                commands.remove_component::<Health>(id);
+                commands.spawn(EntityBuilder::new().with(Health(100)).build());
+                commands.insert_resource(Score(1));
+            }
+        }
+        let after = scene.query::<&Health>().count();
+        SEEN.lock().unwrap().push((before, after));
+    }
+}
+
+#[test]
+fn queued_commands_are_applied_after_the_system() {
+    let mut scene = SceneBuilder::new()
+        .with_entity(EntityBuilder::new().with(Health(0)).build())
+        .with_entity(EntityBuilder::new().with(Health(5)).build())
+        .with_system("update", Reaper)
+        .build();
+    let mut world = World::new();
+    scene.tick_systems("update", &mut world);
+
+    assert_eq!(*SEEN.lock().unwrap(), vec![(2, 2)]);
+    assert!(scene.commands().is_empty());
+    let mut health: Vec<i32> = scene.query::<&Health>().map(|(_, h)| h.0).collect();
+    health.sort();
+    assert_eq!(health, vec![5, 100]);
+    assert_eq!(scene.query_filtered::<(), With<Dead>>().count(), 1);
+    assert_eq!(world.read_resource::<Score>().unwrap().0, 1);
+}
+
+/** The health observed by the queued custom command */
+static OBSERVED: Mutex<Vec<i32>> = Mutex::new(vec![]);
+
+#[test]
+fn commands_run_in_the_order_they_were_queued() {
+    let mut scene = Scene::new();
+    let id = scene.add_entity(EntityBuilder::new().with(Health(1)).build());
+    let mut world = World::new();
+    {
+        let mut commands = scene.commands();
+        commands.insert_component(id, Health(2));
+        commands.add(move |scene, _| {
+            let health = scene
+                .get_entity(id)
+                .unwrap()
+                .get_component::<Health>()
+                .unwrap()
+                .0;
+            OBSERVED.lock().unwrap().push(health);
+        });
+        commands.despawn(id);
+        commands.insert_component(id, Health(3));
+        assert_eq!(commands.len(), 4);
+    }
+    scene.apply_commands(&mut world);
+    assert_eq!(*OBSERVED.lock().unwrap(), vec![2]);
+    // Commands for the despawned entity are dropped
+    assert!(scene.get_entity(id).is_none());
+    assert_eq!(scene.query::<&Health>().count(), 0);
+}
@@ -0,0 +1,144 @@
+use gallium::*;
+
+#[gallium::component]
+struct Health(i32);
+
+#[test]
+fn reused_slots_get_a_new_generation() {
+    let mut scene = Scene::new();
+    let old = scene.add_entity(EntityBuilder::new().with(Health(1)).build());
+    assert!(scene.despawn(old));
+
+    let new = scene.add_entity(EntityBuilder::new().with(Health(2)).build());
+    assert_eq!(new.index(), old.index());
+    assert_ne!(new.generation(), old.generation());
+    assert!(scene.get_entity(old).is_none());
+    let health = scene
+        .get_entity(new)
+        .unwrap()
+        .get_component::<Health>()
+        .unwrap()
+        .0;
+    assert_eq!(health, 2);
+}
+
+#[test]
+fn stale_ids_are_rejected() {
+    let mut scene = Scene::new();
+    let old = scene.add_entity(EntityBuilder::new().with(Health(1)).build());
+    scene.despawn(old);
+    let new = scene.add_entity(EntityBuilder::new().with(Health(2)).build());
+
+    assert!(!scene.insert_component(old, Health(3)));
+    assert!(scene.remove_component::<Health>(old).is_none());
+    assert!(!scene.despawn(old));
+    assert_eq!(
+        scene
+            .get_entity(new)
+            .unwrap()
+            .get_component::<Health>()
+            .unwrap()
+            .0,
+        2
+    );
+}
+
+#[test]
+fn despawned_entities_leave_queries() {
+    let mut scene = Scene::new();
+    let ids: Vec<EntityId> = (0..4)
+        .map(|i| scene.add_entity(EntityBuilder::new().with(Health(i)).build()))
+        .collect();
+    assert!(scene.despawn(ids[1]));
+    assert!(!scene.despawn(ids[1]));
+
+    let removed = scene.despawn_where(|e| e.get_component::<Health>().unwrap().0 >= 2);
+    assert_eq!(removed, 2);
+    let left: Vec<EntityId> = scene.query::<&Health>().map(|(id, _)| id).collect();
+    assert_eq!(left, vec![ids[0]]);
+    assert_eq!(scene.get_entities().len(), 1);
+}
+
+#[gallium::component]
+struct Armor(i32);
+
+#[test]
+fn components_are_inserted_and_removed_in_a_scene() {
+    let mut scene = Scene::new();
+    let id = scene.add_entity(EntityBuilder::new().with(Health(10)).build());
+
+    assert!(scene.insert_component(id, Armor(5)));
+    let entity = scene.get_entity(id).unwrap();
+    assert_eq!(entity.get_component::<Health>().unwrap().0, 10);
+    assert_eq!(entity.get_component::<Armor>().unwrap().0, 5);
+
+    // Inserting again replaces the component
+    assert!(scene.insert_component(id, Armor(7)));
+    assert_eq!(
+        scene
+            .query::<&Armor>()
+            .map(|(_, a)| a.0)
+            .collect::<Vec<i32>>(),
+        vec![7]
+    );
+
+    assert_eq!(scene.remove_component::<Armor>(id).map(|a| a.0), Some(7));
+    assert!(scene.remove_component::<Armor>(id).is_none());
+    let entity = scene.get_entity(id).unwrap();
+    assert!(!entity.has_component::<Armor>());
+    assert_eq!(entity.get_component::<Health>().unwrap().0, 10);
+}
+
+#[test]
+fn components_are_inserted_and_removed_on_an_entity() {
+    let mut entity = EntityBuilder::new().with(Health(1)).build();
+    entity.insert_component(Armor(2));
+    entity.insert_component(Health(3));
+    assert_eq!(entity.get_component::<Health>().unwrap().0, 3);
+    assert_eq!(entity.remove_component::<Armor>().map(|a| a.0), Some(2));
+    assert!(!entity.has_component::<Armor>());
+}
+
+#[gallium::component]
+struct Follow {
+    target: EntityId,
+}
+
+#[test]
+fn stale_ids_stay_invalid_after_saving() {
+    let mut scene = Scene::new();
+    let old = scene.add_entity(EntityBuilder::new().with(Health(1)).build());
+    let keeper = scene.add_entity(EntityBuilder::new().with(Follow { target: old }).build());
+    assert!(scene.despawn(old));
+
+    let mut scene = Scene::from_ron(scene.to_ron().unwrap()).unwrap();
+    let new = scene.add_entity(EntityBuilder::new().with(Health(2)).build());
+    assert_eq!(new.index(), old.index());
+    assert_ne!(new, old);
+    assert!(scene.get_entity(old).is_none());
+
+    // The saved reference to the despawned entity doesn't resolve to the new entity
+    let target = scene
+        .get_entity(keeper)
+        .unwrap()
+        .get_component::<Follow>()
+        .unwrap()
+        .target;
+    assert!(scene.get_entity(target).is_none());
+}
+
+#[test]
+fn stale_ids_stay_invalid_in_scenes_saved_without_free_slots() {
+    let mut scene = Scene::new();
+    let old = scene.add_entity(EntityBuilder::new().with(Health(1)).build());
+    scene.add_entity(EntityBuilder::new().with(Health(2)).build());
+    assert!(scene.despawn(old));
+
+    // Older files end at the entities
+    let ron = scene.to_ron().unwrap();
+    let ron = format!("{})", &ron[..ron.rfind("free:").unwrap()]);
+    let mut scene = Scene::from_ron(ron).unwrap();
+    let new = scene.add_entity(EntityBuilder::new().with(Health(3)).build());
+    assert_eq!(new.index(), old.index());
+    assert!(scene.get_entity(old).is_none());
+}
@@ -0,0 +1,204 @@
+use gallium::*;
+
+#[gallium::component]
+struct Name(String);
+
+#[gallium::component(map_entities)]
+struct Follow {
+    target: EntityId,
+    distance: f32,
+}
+
+/** Get the name of the entity */
+fn name_of(scene: &Scene, id: EntityId) -> String {
+    return scene
+        .get_entity(id)
+        .unwrap()
+        .get_component::<Name>()
+        .unwrap()
+        .0
+        .clone();
+}
+
+/** Construct the ron of an entity saved with the id, with the components written out */
+fn saved_entity(index: usize, generation: u32, stored: &str, components: &str) -> Entity {
+    return Entity::from_ron(&format!(
+        "(id: (index: {}, generation: {}), is_active: true, stored_components: [{}], components: [{}])",
+        index, generation, stored, components
+    ));
+}
+
+#[test]
+fn loaded_entities_are_remapped_to_their_new_ids() {
+    let leader = saved_entity(
+        4,
+        2,
+        r#""entity_mapping::Name""#,
+        r#"{"type": "Name", "value": "leader"}"#,
+    );
+    let follower = saved_entity(
+        7,
+        0,
+        r#""entity_mapping::Follow""#,
+        r#"{"type": "Follow", "target": (index: 4, generation: 2), "distance": 1.5}"#,
+    );
+
+    let mut scene = Scene::new();
+    for _ in 0..5 {
+        scene.add_entity(EntityBuilder::new().build());
+    }
+    let map = scene.add_entities(vec![follower, leader]);
+    assert_eq!(map.len(), 2);
+    let leader = map.get(EntityId::new(4, 2)).unwrap();
+    let follower = map.get(EntityId::new(7, 0)).unwrap();
+    assert_ne!(leader, EntityId::new(4, 2));
+
+    let entity = scene.get_entity(follower).unwrap();
+    let follow = entity.get_component::<Follow>().unwrap();
+    assert_eq!(follow.target, leader);
+    assert_eq!(follow.distance, 1.5);
+    assert_eq!(name_of(&scene, follow.target), "leader");
+}
+
+#[test]
+fn references_survive_a_scene_round_trip() {
+    let mut scene = Scene::new();
+    // Leave holes so the loaded entities can't keep their ids by accident
+    let holes: Vec<EntityId> = (0..3)
+        .map(|_| scene.add_entity(EntityBuilder::new().build()))
+        .collect();
+    let leader = scene.add_entity(
+        EntityBuilder::new()
+            .with(Name(String::from("leader")))
+            .build(),
+    );
+    let follower = EntityBuilder::new()
+        .with(Name(String::from("follower")))
+        .with(Follow {
+            target: leader,
+            distance: 2.0,
+        })
+        .build();
+    scene.add_entity(follower);
+    for hole in holes {
+        scene.despawn(hole);
+    }
+
+    let loaded = Scene::from_ron(scene.to_ron().unwrap()).unwrap();
+    let targets: Vec<EntityId> = loaded.query::<&Follow>().map(|(_, f)| f.target).collect();
+    assert_eq!(targets.len(), 1);
+    assert_eq!(name_of(&loaded, targets[0]), "leader");
+}
+
+#[test]
+fn references_to_entities_outside_the_batch_become_the_placeholder() {
+    let follower = saved_entity(
+        7,
+        0,
+        r#""entity_mapping::Follow""#,
+        r#"{"type": "Follow", "target": (index: 2, generation: 0), "distance": 1.0}"#,
+    );
+
+    let mut scene = Scene::new();
+    for i in 0..5 {
+        scene.add_entity(
+            EntityBuilder::new()
+                .with(Name(format!("local{}", i)))
+                .build(),
+        );
+    }
+    let follower = scene.add_entity(follower);
+    let target = scene
+        .get_entity(follower)
+        .unwrap()
+        .get_component::<Follow>()
+        .unwrap()
+        .target;
+    assert_eq!(target, EntityId::PLACEHOLDER);
+    assert!(scene.get_entity(target).is_none());
+}
+
+#[test]
+fn built_entities_keep_their_references() {
+    let mut scene = Scene::new();
+    let leader = scene.add_entity(
+        EntityBuilder::new()
+            .with(Name(String::from("leader")))
+            .build(),
+    );
+    let follower = EntityBuilder::new()
+        .with(Follow {
+            target: leader,
+            distance: 1.0,
+        })
+        .build();
+    let loaded = saved_entity(
+        3,
+        0,
+        r#""entity_mapping::Name""#,
+        r#"{"type": "Name", "value": "loaded"}"#,
+    );
+    scene.add_entities(vec![follower, loaded]);
+
+    let targets: Vec<EntityId> = scene.query::<&Follow>().map(|(_, f)| f.target).collect();
+    assert_eq!(targets, vec![leader]);
+}
+
+#[test]
+fn scene_builders_map_references_between_their_entities() {
+    let leader = saved_entity(
+        4,
+        1,
+        r#""entity_mapping::Name""#,
+        r#"{"type": "Name", "value": "leader"}"#,
+    );
+    let follower = saved_entity(
+        0,
+        0,
+        r#""entity_mapping::Follow""#,
+        r#"{"type": "Follow", "target": (index: 4, generation: 1), "distance": 3.0}"#,
+    );
+
+    let scene = SceneBuilder::new()
+        .with_entity(follower)
+        .with_entity(leader)
+        .build();
+    let targets: Vec<EntityId> = scene.query::<&Follow>().map(|(_, f)| f.target).collect();
+    assert_eq!(targets.len(), 1);
+    assert_eq!(name_of(&scene, targets[0]), "leader");
+}
+
+/** Caches ids by name, not a reference the mapping knows how to rewrite */
+#[derive(Serialize, Deserialize, Default)]
+struct MyEntityIdCache {
+    names: Vec<String>,
+}
+
+#[gallium::component(map_entities)]
+struct Squad {
+    leader: gallium::EntityId,
+    members: Vec<EntityId>,
+    medic: Option<EntityId>,
+    cache: MyEntityIdCache,
+}
+
+#[test]
+fn entity_fields_are_found_by_their_type_path() {
+    let mut squad = Squad {
+        leader: EntityId::new(1, 0),
+        members: vec![EntityId::new(2, 0), EntityId::new(9, 0)],
+        medic: Some(EntityId::new(2, 0)),
+        cache: MyEntityIdCache::default(),
+    };
+    let mut map = EntityMap::new();
+    map.insert(EntityId::new(1, 0), EntityId::new(5, 0));
+    map.insert(EntityId::new(2, 0), EntityId::new(6, 0));
+    MapEntities::map_entities(&mut squad, &map);
+
+    assert_eq!(squad.leader, EntityId::new(5, 0));
+    assert_eq!(
+        squad.members,
+        vec![EntityId::new(6, 0), EntityId::PLACEHOLDER]
+    );
+    assert_eq!(squad.medic, Some(EntityId::new(6, 0)));
+}
@@ -0,0 +1,105 @@
+use gallium::*;
+use std::sync::Mutex;
+
+/** An event carrying a number */
+struct Ping(u32);
+
+/** Sends a ping with the next number every tick */
+#[derive(Serialize, Deserialize)]
+struct Pinger {
+    next: u32,
+}
+
+#[gallium::system]
+impl System for Pinger {
+    fn tick(&mut self, _scene: &mut Scene, world: &mut World) {
+        self.next += 1;
+        world.event_writer::<Ping>().send(Ping(self.next));
+    }
+}
+
+/** The pings read by each listener, by its index */
+static READ: Mutex<[Vec<u32>; 2]> = Mutex::new([vec![], vec![]]);
+
+/** Records the pings it reads */
+#[derive(Serialize, Deserialize)]
+struct Listener(usize);
+
+#[gallium::system]
+impl System for Listener {
+    fn tick(&mut self, _scene: &mut Scene, world: &mut World) {
+        let pings: Vec<u32> = world.event_reader::<Ping>().iter().map(|p| p.0).collect();
+        READ.lock().unwrap()[self.0].extend(pings);
+    }
+}
+
+#[test]
+fn every_system_reads_each_event_once() {
+    let mut world = World::new();
+    world.add_event::<Ping>();
+    let scene = SceneBuilder::new()
+        .with_system("update", Listener(0))
+        .with_system("update", Pinger { next: 0 })
+        .with_system("update", Listener(1))
+        .build();
+    world.set_scene(scene);
+    for _ in 0..3 {
+        world.tick_systems("update");
+        world.update_events();
+    }
+
+    let read = READ.lock().unwrap();
+    // The first listener reads each ping on the tick after it was sent
+    assert_eq!(read[0], vec![1, 2]);
+    assert_eq!(read[1], vec![1, 2, 3]);
+}
+
+#[test]
+fn events_expire_after_two_updates() {
+    let mut world = World::new();
+    world.add_event::<Ping>();
+    world.send_event(Ping(1));
+    world.update_events();
+    world.send_event(Ping(2));
+    assert!(world.has_unread_events::<Ping>());
+    world.update_events();
+
+    let read: Vec<u32> = world.event_reader::<Ping>().iter().map(|p| p.0).collect();
+    assert_eq!(read, vec![2]);
+    assert!(!world.has_unread_events::<Ping>());
+    assert_eq!(world.event_reader::<Ping>().iter().count(), 0);
+}
+
+/** The pings read by the listener of the hand ticked scene */
+static HAND_READ: Mutex<Vec<u32>> = Mutex::new(vec![]);
+
+/** Records the pings it reads */
+#[derive(Serialize, Deserialize)]
+struct HandListener;
+
+#[gallium::system]
+impl System for HandListener {
+    fn tick(&mut self, _scene: &mut Scene, world: &mut World) {
+        let pings: Vec<u32> = world.event_reader::<Ping>().iter().map(|p| p.0).collect();
+        HAND_READ.lock().unwrap().extend(pings);
+    }
+}
+
+#[test]
+fn ticking_by_hand_updates_the_events() {
+    let mut world = World::new();
+    world.add_event::<Ping>();
+    let scene = SceneBuilder::new()
+        .with_system("update", Pinger { next: 0 })
+        .with_system("render", HandListener)
+        .build();
+    world.set_scene(scene);
+    for _ in 0..5 {
+        world.tick_systems("update");
+        world.tick_systems("render");
+    }
+
+    // Only the events of the last two frames are kept, each read once
+    assert_eq!(world.read_resource::<Events<Ping>>().unwrap().len(), 2);
+    assert_eq!(*HAND_READ.lock().unwrap(), vec![1, 2, 3, 4, 5]);
+}
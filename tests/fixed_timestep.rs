@@ -0,0 +1,64 @@
+use gallium::*;
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+static STEPS: AtomicUsize = AtomicUsize::new(0);
+
+/** Counts its steps */
+#[derive(Serialize, Deserialize)]
+struct Physics;
+
+#[gallium::system]
+impl System for Physics {
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {
+        STEPS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn elapsed_time_runs_whole_steps() {
+    let mut world = World::new();
+    let scene = SceneBuilder::new().with_system("physics", Physics).build();
+    world.set_scene(scene);
+    world.set_fixed_timestep("physics", FixedTimestep::new(Duration::from_millis(10)));
+    assert_eq!(world.tick_fixed("physics", Duration::from_millis(25)), 2);
+    assert_eq!(world.tick_fixed("physics", Duration::from_millis(5)), 1);
+    assert_eq!(world.tick_fixed("physics", Duration::from_millis(4)), 0);
+    assert_eq!(STEPS.load(Ordering::SeqCst), 3);
+    let timestep = world.fixed_timestep("physics").unwrap();
+    assert_eq!(timestep.accumulator(), Duration::from_millis(4));
+    assert!((timestep.alpha() - 0.4).abs() < 1e-9);
+}
+
+#[test]
+fn steps_are_capped_and_the_excess_dropped() {
+    let mut world = World::new();
+    world.set_scene(Scene::new());
+    let timestep = FixedTimestep::new(Duration::from_millis(10)).with_max_steps(3);
+    world.set_fixed_timestep("idle", timestep);
+    assert_eq!(world.tick_fixed("idle", Duration::from_millis(1005)), 3);
+    assert_eq!(
+        world.fixed_timestep("idle").unwrap().accumulator(),
+        Duration::from_millis(5)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Fixed timestep needs a positive amount of steps per second, got: 0")]
+fn zero_hz_is_rejected() {
+    FixedTimestep::from_hz(0.0);
+}
+
+#[test]
+#[should_panic(expected = "Fixed timestep needs a positive amount of steps per second, got: -30")]
+fn negative_hz_is_rejected() {
+    FixedTimestep::from_hz(-30.0);
+}
+
+#[test]
+#[should_panic(expected = "Fixed timestep can't have a step of zero")]
+fn zero_steps_are_rejected() {
+    FixedTimestep::new(Duration::ZERO);
+}
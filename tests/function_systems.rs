@@ -0,0 +1,123 @@
+use gallium::*;
+
+#[gallium::component]
+struct Pos(f32);
+
+#[gallium::component]
+struct Vel(f32);
+
+/** The amount of times the counting closures ran */
+struct Counts(u32, u32);
+
+/** The time each tick simulates */
+struct Step(f32);
+
+fn movement(query: Query<(&mut Pos, &Vel)>, step: Res<Step>) {
+    for (_, (mut pos, vel)) in query {
+        pos.0 += vel.0 * step.0;
+    }
+}
+
+fn spawner(mut commands: SystemCommands) {
+    commands.spawn(EntityBuilder::new().with(Pos(0.0)).build());
+}
+
+fn reset<T: 'static + Component>(_query: Query<&mut T>) {}
+
+fn conflicting(_a: Query<&mut Pos>, _b: Query<&Pos>) {}
+
+fn positions(scene: &Scene) -> Vec<f32> {
+    return scene.query::<&Pos>().map(|(_, pos)| pos.0).collect();
+}
+
+#[test]
+fn parameters_are_fetched_from_the_scene_and_world() {
+    let mut scene = SceneBuilder::new()
+        .with_entity(EntityBuilder::new().with(Pos(1.0)).with(Vel(2.0)).build())
+        .with_entity(EntityBuilder::new().with(Pos(1.0)).build())
+        .with_system("update", movement)
+        .build();
+    let mut world = World::new();
+    world.insert_resource(Step(0.5));
+    scene.tick_systems("update", &mut world);
+    scene.tick_systems("update", &mut world);
+    let mut positions = positions(&scene);
+    positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(positions, vec![1.0, 3.0]);
+}
+
+#[test]
+fn commands_parameter_queues_structural_changes() {
+    let mut scene = SceneBuilder::new().with_system("update", spawner).build();
+    let mut world = World::new();
+    scene.tick_systems("update", &mut world);
+    scene.tick_systems("update", &mut world);
+    assert_eq!(positions(&scene).len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "FunctionSystem::named")]
+fn closures_need_a_name() {
+    SceneBuilder::new().with_system("update", |_query: Query<&Pos>| {});
+}
+
+#[test]
+fn named_closures_are_saved_by_their_name() {
+    let scene = SceneBuilder::new()
+        .with_system(
+            "update",
+            FunctionSystem::named("count_first", |mut c: ResMut<Counts>| c.0 += 1),
+        )
+        .with_system(
+            "update",
+            FunctionSystem::named("count_second", |mut c: ResMut<Counts>| c.1 += 1),
+        )
+        .build();
+    assert_eq!(
+        scene.system_labels("update"),
+        vec!["count_first", "count_second"]
+    );
+    let mut scene = Scene::from_ron(scene.to_ron().unwrap()).unwrap();
+    let mut world = World::new();
+    world.insert_resource(Counts(0, 0));
+    scene.tick_systems("update", &mut world);
+    let counts = world.read_resource::<Counts>().unwrap();
+    assert_eq!((counts.0, counts.1), (1, 1));
+}
+
+#[test]
+#[should_panic(expected = "is already used by another function")]
+fn names_are_unique() {
+    FunctionSystem::named("duplicate", |_query: Query<&Pos>| {});
+    FunctionSystem::named("duplicate", |_query: Query<&Vel>| {});
+}
+
+#[test]
+fn generic_functions_are_labeled_without_their_arguments() {
+    let scene = SceneBuilder::new()
+        .with_ordered_system("update", movement, SystemOrder::new().after("reset"))
+        .with_system("update", reset::<Pos>)
+        .build();
+    assert_eq!(scene.system_labels("update"), vec!["reset", "movement"]);
+}
+
+#[test]
+#[should_panic(expected = "borrows")]
+fn conflicting_parameters_panic() {
+    FunctionSystem::new(conflicting);
+}
+
+#[test]
+fn functions_are_saved_by_their_path() {
+    let scene = SceneBuilder::new()
+        .with_entity(EntityBuilder::new().with(Pos(0.0)).with(Vel(1.0)).build())
+        .with_system("update", movement)
+        .build();
+    let ron = scene.to_ron().unwrap();
+    assert!(ron.contains("function_systems::movement"));
+    let mut scene = Scene::from_ron(ron).unwrap();
+    let mut world = World::new();
+    world.insert_resource(Step(1.0));
+    scene.tick_systems("update", &mut world);
+    assert_eq!(positions(&scene), vec![1.0]);
+}
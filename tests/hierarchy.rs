@@ -0,0 +1,93 @@
+use gallium::*;
+
+#[gallium::component]
+struct Name(String);
+
+/** Add an entity with the name to the scene */
+fn spawn(scene: &mut Scene, name: &str) -> EntityId {
+    return scene.add_entity(EntityBuilder::new().with(Name(String::from(name))).build());
+}
+
+/** Get the id of the entity with the name */
+fn find(scene: &Scene, name: &str) -> EntityId {
+    return scene
+        .query::<&Name>()
+        .find(|(_, n)| n.0 == name)
+        .map(|(id, _)| id)
+        .unwrap();
+}
+
+#[test]
+fn parents_and_children_are_kept_in_sync() {
+    let mut scene = Scene::new();
+    let (a, b, c) = (
+        spawn(&mut scene, "a"),
+        spawn(&mut scene, "b"),
+        spawn(&mut scene, "c"),
+    );
+    assert!(scene.set_parent(b, a));
+    assert!(scene.set_parent(c, b));
+    assert_eq!(scene.get_parent(c), Some(b));
+    assert_eq!(scene.get_children(a), vec![b]);
+    assert_eq!(scene.descendants(a), vec![b, c]);
+
+    // Cycles are rejected
+    assert!(!scene.set_parent(a, c));
+    assert!(!scene.set_parent(a, a));
+    assert_eq!(scene.get_parent(a), None);
+
+    // Reparenting moves the child between the children lists
+    assert!(scene.set_parent(c, a));
+    assert_eq!(scene.get_children(a), vec![b, c]);
+    assert!(scene.get_children(b).is_empty());
+    assert_eq!(scene.remove_parent(c), Some(a));
+    assert_eq!(scene.get_children(a), vec![b]);
+}
+
+#[test]
+fn despawning_detaches_or_removes_the_descendants() {
+    let mut scene = Scene::new();
+    let (a, b, c, d) = (
+        spawn(&mut scene, "a"),
+        spawn(&mut scene, "b"),
+        spawn(&mut scene, "c"),
+        spawn(&mut scene, "d"),
+    );
+    scene.set_parent(b, a);
+    scene.set_parent(c, b);
+    scene.set_parent(d, a);
+
+    // Despawning detaches the entity from its parent and its children
+    assert!(scene.despawn(d));
+    assert_eq!(scene.get_children(a), vec![b]);
+    assert!(scene.despawn(b));
+    assert!(scene.get_children(a).is_empty());
+    assert_eq!(scene.get_parent(c), None);
+
+    // Despawning recursively removes the whole subtree
+    scene.set_parent(c, a);
+    let e = spawn(&mut scene, "e");
+    assert!(scene.despawn_recursive(a));
+    assert!(scene.get_entity(a).is_none());
+    assert!(scene.get_entity(c).is_none());
+    assert!(scene.get_entity(e).is_some());
+    assert!(!scene.despawn_recursive(a));
+}
+
+#[test]
+fn hierarchies_survive_a_ron_round_trip() {
+    let mut scene = Scene::new();
+    let (a, b, c) = (
+        spawn(&mut scene, "a"),
+        spawn(&mut scene, "b"),
+        spawn(&mut scene, "c"),
+    );
+    scene.set_parent(b, a);
+    scene.set_parent(c, a);
+
+    let loaded = Scene::from_ron(scene.to_ron().unwrap()).unwrap();
+    let (a, b, c) = (find(&loaded, "a"), find(&loaded, "b"), find(&loaded, "c"));
+    assert_eq!(loaded.get_children(a), vec![b, c]);
+    assert_eq!(loaded.get_parent(b), Some(a));
+    assert_eq!(loaded.get_parent(c), Some(a));
+}
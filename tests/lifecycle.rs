@@ -0,0 +1,91 @@
+use gallium::*;
+use std::sync::Mutex;
+
+/** The messages logged by each test, by its index */
+static LOGS: Mutex<[Vec<String>; 3]> = Mutex::new([vec![], vec![], vec![]]);
+
+/** Logs its message to the log with its index */
+#[derive(Serialize, Deserialize)]
+struct Log(usize, String);
+
+#[gallium::system]
+impl System for Log {
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {
+        LOGS.lock().unwrap()[self.0].push(self.1.clone());
+    }
+}
+
+/** Construct a scene logging when it starts, ticks and exits */
+fn scene(log: usize, name: &str) -> Scene {
+    return SceneBuilder::new()
+        .with_startup_system(Log(log, format!("{} startup", name)))
+        .with_system("update", Log(log, format!("{} update", name)))
+        .with_exit_system(Log(log, format!("{} exit", name)))
+        .build();
+}
+
+/** Take the messages logged to the log */
+fn take_log(log: usize) -> Vec<String> {
+    return std::mem::take(&mut LOGS.lock().unwrap()[log]);
+}
+
+#[test]
+fn startup_and_exit_systems_run_when_scenes_change() {
+    let mut world = World::new();
+    world.set_scene(scene(0, "a"));
+    world.tick_systems("update");
+    world.set_scene(scene(0, "b"));
+    world.tick_systems("update");
+    let b = world.pop_scene().unwrap();
+    assert!(b.has_started());
+    assert_eq!(
+        take_log(0),
+        vec![
+            "a startup",
+            "a update",
+            "a exit",
+            "b startup",
+            "b update",
+            "b exit"
+        ]
+    );
+}
+
+#[test]
+fn started_scenes_dont_start_again() {
+    let mut world = World::new();
+    world.set_scene(scene(1, "a"));
+    let mut saved = Scene::from_ron(world.pop_scene().unwrap().to_ron().unwrap()).unwrap();
+    assert!(saved.has_started());
+    assert_eq!(take_log(1), vec!["a startup", "a exit"]);
+
+    // Reloading a started scene doesn't run the startup systems again
+    world.set_scene(saved);
+    assert!(take_log(1).is_empty());
+
+    // Until it is restarted
+    saved = world.pop_scene().unwrap();
+    saved.restart();
+    world.set_scene(saved);
+    assert_eq!(take_log(1), vec!["a exit", "a startup"]);
+}
+
+#[test]
+fn pushed_scenes_start_and_exit_on_their_own() {
+    let mut world = World::new();
+    world.set_scene(scene(2, "game"));
+    world.push_scene(scene(2, "menu"), LayerMode::frozen());
+    world.tick_systems("update");
+    world.pop_scene();
+    world.tick_systems("update");
+    assert_eq!(
+        take_log(2),
+        vec![
+            "game startup",
+            "menu startup",
+            "menu update",
+            "menu exit",
+            "game update"
+        ]
+    );
+}
@@ -0,0 +1,42 @@
+use gallium::*;
+use std::sync::Mutex;
+
+/** The saved and skipped counts of each tick of the counter */
+static COUNTS: Mutex<Vec<(u32, u32)>> = Mutex::new(vec![]);
+
+/** Counts its ticks, once in a saved field and once in a skipped field */
+#[derive(Serialize, Deserialize)]
+struct Counter {
+    saved: u32,
+    #[serde(skip)]
+    skipped: u32,
+}
+
+#[gallium::system]
+impl System for Counter {
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {
+        self.saved += 1;
+        self.skipped += 1;
+        COUNTS.lock().unwrap().push((self.saved, self.skipped));
+    }
+}
+
+#[test]
+fn fields_persist_between_ticks_and_through_saving() {
+    let mut world = World::new();
+    let mut scene = SceneBuilder::new()
+        .with_system(
+            "update",
+            Counter {
+                saved: 0,
+                skipped: 0,
+            },
+        )
+        .build();
+    scene.tick_systems("update", &mut world);
+    scene.tick_systems("update", &mut world);
+
+    let mut loaded = Scene::from_ron(scene.to_ron().unwrap()).unwrap();
+    loaded.tick_systems("update", &mut world);
+    assert_eq!(*COUNTS.lock().unwrap(), vec![(1, 1), (2, 2), (3, 1)]);
+}
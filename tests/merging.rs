@@ -0,0 +1,102 @@
+use gallium::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[gallium::component]
+struct Room(u32);
+
+static MERGED_TICKS: AtomicUsize = AtomicUsize::new(0);
+
+/** Counts its ticks */
+#[derive(Serialize, Deserialize)]
+struct Merged;
+
+#[gallium::system]
+impl System for Merged {
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {
+        MERGED_TICKS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/** Merges a room with its systems into the scene on its first tick */
+#[derive(Serialize, Deserialize)]
+struct Loader {
+    loaded: bool,
+}
+
+#[gallium::system]
+impl System for Loader {
+    fn tick(&mut self, scene: &mut Scene, _world: &mut World) {
+        if self.loaded {
+            return;
+        }
+        self.loaded = true;
+        let room = SceneBuilder::new()
+            .with_entity(EntityBuilder::new().with(Room(1)).build())
+            .with_system("update", Merged)
+            .build();
+        scene.merge(room, true);
+    }
+}
+
+#[test]
+fn systems_merged_while_running_are_added_after() {
+    let mut scene = SceneBuilder::new()
+        .with_system("update", Loader { loaded: false })
+        .build();
+    let mut world = World::new();
+    scene.tick_systems("update", &mut world);
+    assert_eq!(scene.query::<&Room>().count(), 1);
+    assert_eq!(scene.system_labels("update"), vec!["Loader", "Merged"]);
+    scene.tick_systems("update", &mut world);
+    assert_eq!(MERGED_TICKS.load(Ordering::SeqCst), 1);
+}
+
+#[gallium::component(map_entities)]
+struct Door {
+    leads_to: EntityId,
+}
+
+/** Construct a room template with a door leading to the other room */
+fn template() -> (Scene, EntityId, EntityId) {
+    let mut room = Scene::new();
+    let a = room.add_entity(EntityBuilder::new().with(Room(1)).build());
+    let b = room.add_entity(EntityBuilder::new().with(Room(2)).build());
+    room.insert_component(a, Door { leads_to: b });
+    return (room, a, b);
+}
+
+#[test]
+fn instantiated_copies_reference_their_own_entities() {
+    let (room, a, b) = template();
+    let mut level = Scene::new();
+    let first = level.instantiate(&room, false).unwrap();
+    let second = level.instantiate(&room, false).unwrap();
+
+    assert_eq!(level.query::<&Room>().count(), 4);
+    for map in [&first, &second] {
+        let door = level.get_entity(map.get(a).unwrap()).unwrap();
+        assert_eq!(
+            door.get_component::<Door>().unwrap().leads_to,
+            map.get(b).unwrap()
+        );
+    }
+    assert_ne!(first.get(a), second.get(a));
+    // The template is left untouched
+    assert_eq!(room.query::<&Room>().count(), 2);
+}
+
+#[test]
+fn merging_moves_the_systems_if_asked() {
+    let (room, _, _) = template();
+    let mut systems = SceneBuilder::new().with_system("update", Merged).build();
+    systems.merge(room, false);
+    assert_eq!(systems.query::<&Door>().count(), 1);
+
+    let mut level = Scene::new();
+    let (room, _, _) = template();
+    level.merge(room, true);
+    assert!(!level.has_systems("update"));
+    level.merge(systems, true);
+    assert_eq!(level.system_labels("update"), vec!["Merged"]);
+    assert_eq!(level.query::<&Room>().count(), 4);
+}
@@ -0,0 +1,114 @@
+use gallium::*;
+use std::sync::Mutex;
+
+#[gallium::component]
+struct HealthBar(u32);
+
+/** The ticks and events seen by each test, by its index */
+static LOGS: Mutex<[Vec<String>; 3]> = Mutex::new([vec![], vec![], vec![]]);
+
+/** Logs the ticks and events of its scene to the log with its index */
+#[derive(Serialize, Deserialize)]
+struct Log(usize, String);
+
+#[gallium::system]
+impl System for Log {
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {
+        LOGS.lock().unwrap()[self.0].push(format!("{} tick", self.1));
+    }
+
+    fn on_event(
+        &mut self,
+        _scene: &mut Scene,
+        _world: &mut World,
+        _tag: &str,
+        _data: &dyn std::any::Any,
+    ) {
+        LOGS.lock().unwrap()[self.0].push(format!("{} event", self.1));
+    }
+}
+
+/** Construct a scene logging its ticks, events and exit */
+fn scene(log: usize, name: &str) -> Scene {
+    return SceneBuilder::new()
+        .with_system("update", Log(log, String::from(name)))
+        .with_exit_system(Log(log, format!("{} exit", name)))
+        .build();
+}
+
+/** Take the messages logged to the log */
+fn take_log(log: usize) -> Vec<String> {
+    return std::mem::take(&mut LOGS.lock().unwrap()[log]);
+}
+
+#[test]
+fn named_scenes_run_after_the_stack() {
+    let mut world = World::new();
+    world.add_scene("hud", scene(0, "hud"));
+    world.add_scene("minimap", scene(0, "minimap"));
+    world.set_scene(scene(0, "level"));
+    assert_eq!(world.scene_names(), vec!["hud", "minimap"]);
+
+    world.tick_systems("update");
+    assert_eq!(take_log(0), vec!["level tick", "hud tick", "minimap tick"]);
+    world.tick_scenes("update", &["minimap"]);
+    // Events reach every system of the scene, the exit systems included
+    world.dispatch_event_to(&["hud"], "click", &());
+    assert_eq!(
+        take_log(0),
+        vec!["minimap tick", "hud event", "hud exit event"]
+    );
+
+    // Replacing the level leaves the named scenes alone
+    world.set_scene(scene(0, "next"));
+    assert!(world.has_scene("hud"));
+    assert_eq!(take_log(0), vec!["level exit tick"]);
+
+    assert!(world.remove_scene("hud").is_some());
+    assert!(world.remove_scene("hud").is_none());
+    world.tick_systems("update");
+    assert_eq!(
+        take_log(0),
+        vec!["hud exit tick", "next tick", "minimap tick"]
+    );
+}
+
+#[test]
+#[should_panic(expected = "World already has scene named: hud")]
+fn names_are_unique() {
+    let mut world = World::new();
+    world.add_scene("hud", scene(1, "hud"));
+    world.add_scene("hud", scene(1, "other"));
+}
+
+/** The health bars the reader saw in the hud */
+static BARS: Mutex<Vec<u32>> = Mutex::new(vec![]);
+
+/** Reads the health bars of the hud scene */
+#[derive(Serialize, Deserialize)]
+struct ReadHud;
+
+#[gallium::system]
+impl System for ReadHud {
+    fn tick(&mut self, _scene: &mut Scene, world: &mut World) {
+        let hud = world.scene("hud").unwrap();
+        BARS.lock()
+            .unwrap()
+            .extend(hud.query::<&HealthBar>().map(|(_, bar)| bar.0));
+        // The scene of the running system is taken out of the world
+        assert!(world.scene("level").is_none());
+    }
+}
+
+#[test]
+fn systems_look_up_other_named_scenes() {
+    let mut world = World::new();
+    let hud = SceneBuilder::new()
+        .with_entity(EntityBuilder::new().with(HealthBar(80)).build())
+        .build();
+    world.add_scene("hud", hud);
+    let level = SceneBuilder::new().with_system("update", ReadHud).build();
+    world.add_scene("level", level);
+    world.tick_scenes("update", &["level"]);
+    assert_eq!(*BARS.lock().unwrap(), vec![80]);
+}
@@ -0,0 +1,119 @@
+use gallium::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[gallium::component]
+struct A;
+
+#[gallium::component]
+struct B;
+
+/** Queues spawning 10 entities with A */
+#[derive(Serialize, Deserialize)]
+struct Spawner;
+
+#[gallium::system]
+impl System for Spawner {
+    fn access(&self) -> Option<SystemAccess> {
+        return Some(SystemAccess::new());
+    }
+
+    fn tick_parallel(&mut self, scene: &Scene, _world: &World) {
+        for _ in 0..10 {
+            scene.commands().spawn(EntityBuilder::new().with(A).build());
+        }
+    }
+}
+
+static ADDED: AtomicUsize = AtomicUsize::new(0);
+
+/** Counts the entities with A added since it last ran */
+#[derive(Serialize, Deserialize)]
+struct Watcher;
+
+#[gallium::system]
+impl System for Watcher {
+    fn access(&self) -> Option<SystemAccess> {
+        return Some(SystemAccess::new().read::<A>().read::<B>());
+    }
+
+    fn tick_parallel(&mut self, scene: &Scene, _world: &World) {
+        let added = scene.query_filtered::<Option<&B>, Added<A>>().count();
+        ADDED.fetch_add(added, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn systems_in_a_batch_see_each_others_commands() {
+    let mut scene = SceneBuilder::new()
+        .with_system("update", Spawner)
+        .with_system("update", Watcher)
+        .build();
+    let mut world = World::new();
+    scene.tick_systems("update", &mut world);
+    scene.tick_systems("update", &mut world);
+    assert_eq!(ADDED.load(Ordering::SeqCst), 10);
+}
+
+#[gallium::component]
+struct Label(usize);
+
+/** Queues spawning entities labeled with its index, yielding between spawns */
+#[derive(Serialize, Deserialize)]
+struct LabeledSpawner(usize);
+
+#[gallium::system]
+impl System for LabeledSpawner {
+    fn access(&self) -> Option<SystemAccess> {
+        return Some(SystemAccess::new());
+    }
+
+    fn tick_parallel(&mut self, scene: &Scene, _world: &World) {
+        for _ in 0..200 {
+            scene
+                .commands()
+                .spawn(EntityBuilder::new().with(Label(self.0)).build());
+            std::thread::yield_now();
+        }
+    }
+}
+
+#[test]
+fn parallel_commands_apply_in_system_order() {
+    let mut scene = SceneBuilder::new()
+        .with_system("update", LabeledSpawner(0))
+        .with_system("update", LabeledSpawner(1))
+        .with_system("update", LabeledSpawner(2))
+        .build();
+    let mut world = World::new();
+    scene.tick_systems("update", &mut world);
+    let mut labels: Vec<(EntityId, usize)> = scene
+        .query::<&Label>()
+        .map(|(id, label)| (id, label.0))
+        .collect();
+    labels.sort_by_key(|(id, _)| id.index());
+    let labels: Vec<usize> = labels.into_iter().map(|(_, label)| label).collect();
+    let expected: Vec<usize> = (0..3).flat_map(|i| std::iter::repeat_n(i, 200)).collect();
+    assert_eq!(labels, expected);
+}
+
+/** Declares its access but only implements tick */
+#[derive(Serialize, Deserialize)]
+struct TickOnly;
+
+#[gallium::system]
+impl System for TickOnly {
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {}
+
+    fn access(&self) -> Option<SystemAccess> {
+        return Some(SystemAccess::new());
+    }
+}
+
+#[test]
+#[should_panic(
+    expected = "System TickOnly declares its access but doesn't implement tick_parallel"
+)]
+fn declaring_access_requires_tick_parallel() {
+    let mut scene = SceneBuilder::new().with_system("update", TickOnly).build();
+    scene.tick_systems("update", &mut World::new());
+}
@@ -0,0 +1,126 @@
+use gallium::*;
+
+#[gallium::component]
+struct Pos(f32);
+
+#[gallium::component]
+struct Vel(f32);
+
+#[gallium::component]
+struct Player;
+
+/** Construct a scene with a moving player, a still player and a moving entity */
+fn scene() -> (Scene, Vec<EntityId>) {
+    let mut scene = Scene::new();
+    let ids = vec![
+        scene.add_entity(
+            EntityBuilder::new()
+                .with(Pos(0.0))
+                .with(Vel(1.0))
+                .with(Player)
+                .build(),
+        ),
+        scene.add_entity(EntityBuilder::new().with(Pos(2.0)).with(Player).build()),
+        scene.add_entity(EntityBuilder::new().with(Pos(4.0)).with(Vel(-1.0)).build()),
+    ];
+    return (scene, ids);
+}
+
+#[test]
+fn query_writes_are_visible_to_later_queries() {
+    let (scene, ids) = scene();
+    for (_, (mut pos, vel)) in scene.query::<(&mut Pos, &Vel)>() {
+        pos.0 += vel.0;
+    }
+    let positions: Vec<(EntityId, f32)> =
+        scene.query::<&Pos>().map(|(id, pos)| (id, pos.0)).collect();
+    assert_eq!(positions.len(), 3);
+    assert!(positions.contains(&(ids[0], 1.0)));
+    assert!(positions.contains(&(ids[1], 2.0)));
+    assert!(positions.contains(&(ids[2], 3.0)));
+}
+
+#[test]
+fn optional_components_are_fetched_when_present() {
+    let (scene, ids) = scene();
+    let mut found: Vec<(EntityId, Option<f32>)> = scene
+        .query_filtered::<(&Pos, Option<&Vel>), With<Player>>()
+        .map(|(id, (_, vel))| (id, vel.map(|v| v.0)))
+        .collect();
+    found.sort_by_key(|(id, _)| id.index());
+    assert_eq!(found, vec![(ids[0], Some(1.0)), (ids[1], None)]);
+}
+
+#[test]
+fn filter_only_queries_yield_ids() {
+    let (scene, ids) = scene();
+    let mut found: Vec<EntityId> = scene
+        .query_filtered::<(), (With<Vel>, Without<Player>)>()
+        .map(|(id, _)| id)
+        .collect();
+    assert_eq!(found, vec![ids[2]]);
+
+    found = scene.query::<()>().map(|(id, _)| id).collect();
+    assert_eq!(found.len(), 3);
+}
+
+#[test]
+fn filter_only_query_params_yield_ids() {
+    let (mut scene, _) = scene();
+    scene
+        .add_system(
+            "update",
+            FunctionSystem::named("count_players", |players: Query<(), With<Player>>| {
+                assert_eq!(players.iter().count(), 2);
+            }),
+        )
+        .unwrap();
+    let mut world = World::new();
+    world.set_scene(scene);
+    world.tick_systems("update");
+}
+
+/** Get the sorted ids matching the filter */
+fn matching<F: for<'a> QueryFilter<'a>>(scene: &Scene) -> Vec<EntityId> {
+    let mut ids: Vec<EntityId> = scene.query_filtered::<(), F>().map(|(id, _)| id).collect();
+    ids.sort_by_key(|id| id.index());
+    return ids;
+}
+
+#[test]
+fn filters_narrow_down_the_entities() {
+    let (scene, ids) = scene();
+    assert_eq!(matching::<With<Player>>(&scene), vec![ids[0], ids[1]]);
+    assert_eq!(matching::<Without<Vel>>(&scene), vec![ids[1]]);
+    assert_eq!(matching::<(With<Vel>, With<Player>)>(&scene), vec![ids[0]]);
+    assert_eq!(
+        matching::<Or<(Without<Vel>, Without<Player>)>>(&scene),
+        vec![ids[1], ids[2]]
+    );
+}
+
+#[test]
+fn inactive_entities_are_left_out() {
+    let (scene, ids) = scene();
+    scene.get_entity(ids[0]).unwrap().set_active(false);
+    assert_eq!(matching::<With<Player>>(&scene), vec![ids[1]]);
+    assert_eq!(scene.query::<&Vel>().count(), 1);
+}
+
+#[test]
+fn conflicting_queries_are_rejected() {
+    let (scene, _) = scene();
+    let error = scene.try_query::<(&mut Pos, &Pos)>().err().unwrap();
+    assert!(error.contains("Pos"));
+    assert!(scene
+        .try_query_filtered::<(Option<&mut Vel>, &Vel), With<Player>>()
+        .is_err());
+    assert!(scene.try_query::<(&Pos, &Pos, &mut Vel)>().is_ok());
+}
+
+#[test]
+#[should_panic(expected = "borrows")]
+fn conflicting_queries_panic_without_matching_entities() {
+    let scene = Scene::new();
+    scene.query::<(&Pos, &mut Pos)>();
+}
@@ -0,0 +1,123 @@
+use gallium::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[gallium::component]
+struct A;
+
+/** A system that runs once */
+#[derive(Serialize, Deserialize)]
+struct Startup;
+
+#[gallium::system]
+impl System for Startup {
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {}
+}
+
+/** Spawns and despawns an entity with A every tick */
+#[derive(Serialize, Deserialize)]
+struct Churn;
+
+#[gallium::system]
+impl System for Churn {
+    fn tick(&mut self, scene: &mut Scene, _world: &mut World) {
+        let id = scene.add_entity(EntityBuilder::new().with(A).build());
+        scene.despawn(id);
+    }
+}
+
+static SEEN: AtomicUsize = AtomicUsize::new(0);
+
+/** Counts the removals of A it sees */
+#[derive(Serialize, Deserialize)]
+struct Reader;
+
+#[gallium::system]
+impl System for Reader {
+    fn tick(&mut self, scene: &mut Scene, _world: &mut World) {
+        SEEN.fetch_add(scene.removed::<A>().count(), Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn removals_are_pruned_when_systems_run_rarely() {
+    let mut world = World::new();
+    let scene = SceneBuilder::new()
+        .with_startup_system(Startup)
+        .with_system("update", Churn)
+        .build();
+    world.set_scene(scene);
+    for _ in 0..1000 {
+        world.tick_systems("update");
+    }
+    let scene = world.pop_scene().unwrap();
+    // Outside of systems every buffered removal is seen
+    assert!(scene.removed::<A>().count() <= 1);
+}
+
+#[test]
+fn readers_see_every_removal_once() {
+    let mut world = World::new();
+    let scene = SceneBuilder::new()
+        .with_startup_system(Startup)
+        .with_system("update", Reader)
+        .with_system("update", Churn)
+        .with_system("late", Startup)
+        .build();
+    world.set_scene(scene);
+    for _ in 0..100 {
+        world.tick_systems("update");
+    }
+    // The removal of the last tick is seen on the next tick
+    assert_eq!(SEEN.load(Ordering::SeqCst), 99);
+    let scene = world.pop_scene().unwrap();
+    assert_eq!(scene.removed::<A>().count(), 1);
+}
+
+#[gallium::component]
+struct B;
+
+/** Removes B from every entity that has it */
+#[derive(Serialize, Deserialize)]
+struct StripB;
+
+#[gallium::system]
+impl System for StripB {
+    fn tick(&mut self, scene: &mut Scene, _world: &mut World) {
+        let ids: Vec<EntityId> = scene.query::<&B>().map(|(id, _)| id).collect();
+        for id in ids {
+            scene.remove_component::<B>(id);
+        }
+    }
+}
+
+static FIRST_SEEN: AtomicUsize = AtomicUsize::new(0);
+
+/** Counts the removals of B it sees, the first system to read them */
+#[derive(Serialize, Deserialize)]
+struct FirstReader;
+
+#[gallium::system]
+impl System for FirstReader {
+    fn tick(&mut self, scene: &mut Scene, _world: &mut World) {
+        FIRST_SEEN.fetch_add(scene.removed::<B>().count(), Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn unread_removals_are_kept_for_a_full_run_of_the_tags() {
+    let mut world = World::new();
+    let mut scene = SceneBuilder::new()
+        .with_system("early", FirstReader)
+        .with_system("update", StripB)
+        .build();
+    scene.add_entity(EntityBuilder::new().with(B).build());
+    world.set_scene(scene);
+
+    // The removal is made before the reader ever ran, it sees it the first time it runs
+    world.tick_systems("update");
+    world.tick_systems("early");
+    assert_eq!(FIRST_SEEN.load(Ordering::SeqCst), 1);
+    world.tick_systems("update");
+    world.tick_systems("early");
+    assert_eq!(FIRST_SEEN.load(Ordering::SeqCst), 1);
+}
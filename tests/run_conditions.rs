@@ -0,0 +1,157 @@
+use gallium::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[gallium::component]
+struct A;
+
+#[gallium::component]
+struct B;
+
+/** Holds if the flag is set */
+#[derive(Serialize, Deserialize)]
+struct Flag(bool);
+
+#[gallium::condition]
+impl Condition for Flag {
+    fn evaluate(&mut self, _scene: &Scene, _world: &World) -> bool {
+        return self.0;
+    }
+}
+
+/** Counts its ticks in the counter with its index */
+#[derive(Serialize, Deserialize)]
+struct Count(usize);
+
+static COUNTS: [AtomicUsize; 11] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+
+#[gallium::system]
+impl System for Count {
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {
+        COUNTS[self.0].fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn count(index: usize) -> usize {
+    return COUNTS[index].load(Ordering::SeqCst);
+}
+
+#[test]
+fn or_filter_resolves_through_the_glob_import() {
+    let mut scene = Scene::new();
+    scene.add_entity(EntityBuilder::new().with(A).build());
+    scene.add_entity(EntityBuilder::new().with(B).build());
+    scene.add_entity(EntityBuilder::new().build());
+    let matching = scene
+        .query_filtered::<Option<&A>, Or<(With<A>, With<B>)>>()
+        .count();
+    assert_eq!(matching, 2);
+}
+
+#[test]
+fn conditions_compose() {
+    let mut scene = SceneBuilder::new()
+        .with_system_if("update", Count(0), Flag(true).and(Flag(false)))
+        .with_system_if("update", Count(1), Flag(false).or(Flag(true)))
+        .with_system_if("update", Count(2), Flag(false).not())
+        .with_system_if("update", Count(3), Flag(true).not().or(Flag(false)))
+        .with_system_if("update", Count(4), EveryNTicks::new(3))
+        .build();
+    let mut world = World::new();
+    for _ in 0..6 {
+        scene.tick_systems("update", &mut world);
+    }
+    assert_eq!(count(0), 0);
+    assert_eq!(count(1), 6);
+    assert_eq!(count(2), 6);
+    assert_eq!(count(3), 0);
+    assert_eq!(count(4), 2);
+}
+
+#[test]
+fn conditions_round_trip_with_their_system() {
+    let scene = SceneBuilder::new()
+        .with_system_if("update", Count(5), Flag(false).or(Flag(true).not()))
+        .with_system_if("update", Count(6), EveryNTicks::new(2).and(Flag(true)))
+        .build();
+    let mut scene = Scene::from_ron(scene.to_ron().unwrap()).unwrap();
+    let mut world = World::new();
+    for _ in 0..4 {
+        scene.tick_systems("update", &mut world);
+    }
+    assert_eq!(count(5), 0);
+    assert_eq!(count(6), 2);
+}
+
+/** A resource the conditions compare against */
+#[derive(Serialize, Deserialize, PartialEq)]
+struct Mode(u32);
+
+/** An event the conditions wait for */
+struct Ping;
+
+/** Reads the pings, counting its ticks in the counter with its index */
+#[derive(Serialize, Deserialize)]
+struct ReadPings(usize);
+
+#[gallium::system]
+impl System for ReadPings {
+    fn tick(&mut self, _scene: &mut Scene, world: &mut World) {
+        world.event_reader::<Ping>().iter().count();
+        COUNTS[self.0].fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn resource_conditions_check_the_world() {
+    let mut scene = SceneBuilder::new()
+        .with_system_if("update", Count(7), resource_exists::<Mode>())
+        .with_system_if("update", Count(8), resource_equals(Mode(2)))
+        .build();
+    let mut world = World::new();
+    scene.tick_systems("update", &mut world);
+    world.insert_resource(Mode(1));
+    scene.tick_systems("update", &mut world);
+    world.get_resource::<Mode>().unwrap().0 = 2;
+    scene.tick_systems("update", &mut world);
+
+    // Saved conditions compare against the same value once loaded
+    let mut scene = Scene::from_ron(scene.to_ron().unwrap()).unwrap();
+    scene.tick_systems("update", &mut world);
+    world.remove_resource::<Mode>();
+    scene.tick_systems("update", &mut world);
+    assert_eq!(count(7), 3);
+    assert_eq!(count(8), 2);
+}
+
+#[test]
+fn events_pending_holds_until_the_system_reads_them() {
+    let mut scene = SceneBuilder::new()
+        .with_system_if("update", ReadPings(9), events_pending::<Ping>())
+        .with_system_if("update", Count(10), events_pending::<u64>())
+        .build();
+    let mut world = World::new();
+    world.add_event::<Ping>();
+    scene.tick_systems("update", &mut world);
+    world.send_event(Ping);
+    scene.tick_systems("update", &mut world);
+    scene.tick_systems("update", &mut world);
+
+    let mut scene = Scene::from_ron(scene.to_ron().unwrap()).unwrap();
+    world.send_event(Ping);
+    scene.tick_systems("update", &mut world);
+    assert_eq!(count(9), 2);
+    // The world has no u64 events
+    assert_eq!(count(10), 0);
+}
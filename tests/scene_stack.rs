@@ -0,0 +1,104 @@
+use gallium::*;
+use std::sync::Mutex;
+
+/** The ticks and events seen by each test, by its index */
+static LOGS: Mutex<[Vec<String>; 3]> = Mutex::new([vec![], vec![], vec![]]);
+
+/** Logs the ticks and events of its scene to the log with its index */
+#[derive(Serialize, Deserialize)]
+struct Log(usize, String);
+
+#[gallium::system]
+impl System for Log {
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {
+        LOGS.lock().unwrap()[self.0].push(format!("{} tick", self.1));
+    }
+
+    fn on_event(
+        &mut self,
+        _scene: &mut Scene,
+        _world: &mut World,
+        _tag: &str,
+        _data: &dyn std::any::Any,
+    ) {
+        LOGS.lock().unwrap()[self.0].push(format!("{} event", self.1));
+    }
+}
+
+/** Construct a scene logging its ticks and events */
+fn scene(log: usize, name: &str) -> Scene {
+    return SceneBuilder::new()
+        .with_system("update", Log(log, String::from(name)))
+        .build();
+}
+
+/** Tick the world and dispatch an event, returning what was logged */
+fn run(world: &mut World, log: usize) -> Vec<String> {
+    world.tick_systems("update");
+    world.dispatch_event("click", &());
+    return std::mem::take(&mut LOGS.lock().unwrap()[log]);
+}
+
+#[test]
+fn modes_decide_what_reaches_the_scenes_below() {
+    let mut world = World::new();
+    world.set_scene(scene(0, "game"));
+
+    // Overlays tick from the bottom up and dispatch from the top down
+    world.push_scene(scene(0, "hud"), LayerMode::overlay());
+    assert_eq!(
+        run(&mut world, 0),
+        vec!["game tick", "hud tick", "hud event", "game event"]
+    );
+
+    // Frozen scenes stop everything below them
+    world.push_scene(scene(0, "pause"), LayerMode::frozen());
+    assert_eq!(run(&mut world, 0), vec!["pause tick", "pause event"]);
+
+    // Events can be let through on their own
+    world.pop_scene();
+    world.push_scene(scene(0, "dialog"), LayerMode::new().events_below());
+    assert_eq!(
+        run(&mut world, 0),
+        vec!["dialog tick", "dialog event", "hud event", "game event"]
+    );
+    assert_eq!(world.scene_count(), 3);
+}
+
+#[test]
+fn set_scene_replaces_the_whole_stack() {
+    let mut world = World::new();
+    world.set_scene(scene(1, "game"));
+    world.push_scene(scene(1, "hud"), LayerMode::overlay());
+    world.set_scene(scene(1, "menu"));
+    assert_eq!(world.scene_count(), 1);
+    assert_eq!(run(&mut world, 1), vec!["menu tick", "menu event"]);
+    assert!(world.pop_scene().is_some());
+    assert!(world.pop_scene().is_none());
+}
+
+/** Pops its own scene off the world */
+#[derive(Serialize, Deserialize)]
+struct Close;
+
+#[gallium::system]
+impl System for Close {
+    fn tick(&mut self, _scene: &mut Scene, world: &mut World) {
+        // The scene is taken while its systems run, so there is none to return
+        assert!(world.pop_scene().is_none());
+    }
+}
+
+#[test]
+fn scenes_can_pop_themselves() {
+    let mut world = World::new();
+    world.set_scene(scene(2, "game"));
+    let menu = SceneBuilder::new()
+        .with_system("update", Close)
+        .with_exit_system(Log(2, String::from("menu exit")))
+        .build();
+    world.push_scene(menu, LayerMode::frozen());
+    assert_eq!(run(&mut world, 2), vec!["menu exit tick", "game event"]);
+    assert_eq!(world.scene_count(), 1);
+    assert_eq!(run(&mut world, 2), vec!["game tick", "game event"]);
+}
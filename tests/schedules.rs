@@ -0,0 +1,140 @@
+use gallium::*;
+use std::sync::Mutex;
+
+static RUNS: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+/** Records its name each time it ticks */
+#[derive(Serialize, Deserialize)]
+struct Record(String);
+
+#[gallium::system]
+impl System for Record {
+    fn tick(&mut self, _scene: &mut Scene, _world: &mut World) {
+        RUNS.lock().unwrap().push(self.0.clone());
+    }
+}
+
+fn record(name: &str) -> Record {
+    return Record(String::from(name));
+}
+
+/** A scene whose physics system has to run before its input system */
+fn inverted_scene() -> Scene {
+    return SceneBuilder::new()
+        .with_ordered_system(
+            "physics",
+            record("physics"),
+            SystemOrder::new().before("input"),
+        )
+        .with_ordered_system("input", record("input"), SystemOrder::new().label("input"))
+        .build();
+}
+
+fn schedule() -> Schedule {
+    return Schedule::new()
+        .with_stage("input")
+        .with_stage("render")
+        .with_stage_before("physics", "render");
+}
+
+#[test]
+fn stages_run_in_order() {
+    let mut world = World::new();
+    world.set_schedule(schedule()).unwrap();
+    let scene = SceneBuilder::new()
+        .with_system("render", record("stages render"))
+        .with_system("physics", record("stages physics"))
+        .with_system("input", record("stages input"))
+        .build();
+    world.set_scene(scene);
+    world.run_schedule().unwrap();
+    let runs: Vec<String> = RUNS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|r| r.starts_with("stages"))
+        .cloned()
+        .collect();
+    assert_eq!(
+        runs,
+        vec!["stages input", "stages physics", "stages render"]
+    );
+}
+
+#[test]
+fn schedules_conflicting_with_a_scene_are_rejected() {
+    let mut world = World::new();
+    world.set_scene(inverted_scene());
+    assert!(world.set_schedule(schedule()).is_err());
+    assert!(world.schedule().stages().is_empty());
+}
+
+#[test]
+fn scenes_conflicting_with_the_schedule_fail_the_run() {
+    let mut world = World::new();
+    world.set_schedule(schedule()).unwrap();
+    world.set_scene(inverted_scene());
+    let error = world.run_schedule().unwrap_err();
+    assert!(error.contains("physics"));
+    assert!(world.update(std::time::Duration::from_millis(16)).is_err());
+}
+
+#[test]
+fn systems_added_after_the_schedule_are_checked() {
+    let mut world = World::new();
+    world.set_schedule(schedule()).unwrap();
+    world.add_scene("ok", SceneBuilder::new().build());
+    world.set_scene(
+        SceneBuilder::new()
+            .with_system("input", record("checked input"))
+            .build(),
+    );
+    world.run_schedule().unwrap();
+
+    // The physics system has to run before an input system, but is in the later stage
+    world
+        .scene_mut("ok")
+        .unwrap()
+        .add_ordered_system(
+            "input",
+            record("checked early"),
+            SystemOrder::new().label("checked"),
+        )
+        .unwrap();
+    world
+        .scene_mut("ok")
+        .unwrap()
+        .add_ordered_system(
+            "physics",
+            record("checked physics"),
+            SystemOrder::new().before("checked"),
+        )
+        .unwrap();
+    assert!(world.run_schedule().is_err());
+}
+
+#[test]
+fn the_description_lists_the_systems_of_each_stage() {
+    let mut world = World::new();
+    world.set_schedule(schedule()).unwrap();
+    world.set_fixed_timestep(
+        "physics",
+        FixedTimestep::new(std::time::Duration::from_millis(10)).with_max_steps(3),
+    );
+    world.set_scene(
+        SceneBuilder::new()
+            .with_system("render", record("render"))
+            .with_ordered_system("input", record("late"), SystemOrder::new().label("late"))
+            .with_ordered_system(
+                "input",
+                record("early"),
+                SystemOrder::new().label("early").before("late"),
+            )
+            .build(),
+    );
+    assert_eq!(
+        world.describe_schedule(),
+        "input:\n  1. early\n  2. late\nphysics (every 10ms, at most 3 steps):\nrender:\n  1. Record\n"
+    );
+    assert_eq!(schedule().to_string(), "input -> physics -> render");
+}
@@ -0,0 +1,133 @@
+use gallium::*;
+
+#[gallium::component]
+struct Pos(i32);
+
+#[gallium::component]
+struct Vel(i32);
+
+#[gallium::component(storage = "sparse_set")]
+struct Stunned(u32);
+
+/** Get the sorted values of the components of type Pos in the scene */
+fn positions(scene: &Scene) -> Vec<i32> {
+    let mut positions: Vec<i32> = scene.query::<&Pos>().map(|(_, pos)| pos.0).collect();
+    positions.sort();
+    return positions;
+}
+
+#[test]
+fn entities_with_the_same_components_share_an_archetype() {
+    let mut scene = Scene::new();
+    for i in 0..3 {
+        scene.add_entity(EntityBuilder::new().with(Pos(i)).with(Vel(i)).build());
+    }
+    scene.add_entity(EntityBuilder::new().with(Pos(3)).build());
+
+    let sizes: Vec<usize> = scene
+        .archetypes()
+        .filter(|a| a.has_component::<Pos>())
+        .map(|a| a.len())
+        .collect();
+    assert_eq!(sizes.len(), 2);
+    assert!(sizes.contains(&3) && sizes.contains(&1));
+    let both = scene
+        .archetypes()
+        .find(|a| a.has_component::<Vel>())
+        .unwrap();
+    assert_eq!(
+        both.get_column::<Pos>()
+            .unwrap()
+            .get(2)
+            .map(|c| c.read().unwrap().0),
+        Some(2)
+    );
+}
+
+#[test]
+fn components_survive_a_ron_round_trip() {
+    let mut scene = Scene::new();
+    let id = scene.add_entity(EntityBuilder::new().with(Pos(1)).with(Vel(-1)).build());
+    scene.add_entity(EntityBuilder::new().with(Pos(2)).build());
+    scene
+        .get_entity(id)
+        .unwrap()
+        .get_component::<Pos>()
+        .unwrap()
+        .0 = 5;
+
+    let loaded = Scene::from_ron(scene.to_ron().unwrap()).unwrap();
+    assert_eq!(positions(&loaded), vec![2, 5]);
+    let velocities: Vec<i32> = loaded.query::<&Vel>().map(|(_, vel)| vel.0).collect();
+    assert_eq!(velocities, vec![-1]);
+}
+
+#[test]
+fn entities_survive_a_ron_round_trip() {
+    let entity = EntityBuilder::new().with(Pos(4)).with(Vel(2)).build();
+    let loaded = Entity::from_ron(&entity.to_ron());
+    assert_eq!(loaded.get_component::<Pos>().unwrap().0, 4);
+    assert_eq!(loaded.get_component::<Vel>().unwrap().0, 2);
+}
+
+#[test]
+fn sparse_components_dont_move_entities_between_archetypes() {
+    let mut scene = Scene::new();
+    let id = scene.add_entity(EntityBuilder::new().with(Pos(0)).build());
+    let archetypes = scene.archetypes().count();
+
+    assert!(scene.insert_component(id, Stunned(3)));
+    assert_eq!(scene.archetypes().count(), archetypes);
+    assert!(scene.archetypes().all(|a| !a.has_component::<Stunned>()));
+    let stunned: Vec<(EntityId, u32)> = scene
+        .query_filtered::<&Stunned, With<Pos>>()
+        .map(|(id, stunned)| (id, stunned.0))
+        .collect();
+    assert_eq!(stunned, vec![(id, 3)]);
+
+    assert_eq!(scene.remove_component::<Stunned>(id).map(|s| s.0), Some(3));
+    assert_eq!(scene.archetypes().count(), archetypes);
+    assert_eq!(scene.query::<&Stunned>().count(), 0);
+    assert_eq!(positions(&scene), vec![0]);
+}
+
+#[test]
+fn sparse_components_survive_a_ron_round_trip() {
+    let mut scene = Scene::new();
+    scene.add_entity(EntityBuilder::new().with(Pos(1)).with(Stunned(2)).build());
+    scene.add_entity(EntityBuilder::new().with(Pos(3)).build());
+
+    let loaded = Scene::from_ron(scene.to_ron().unwrap()).unwrap();
+    let stunned: Vec<(i32, u32)> = loaded
+        .query::<(&Pos, &Stunned)>()
+        .map(|(_, (pos, stunned))| (pos.0, stunned.0))
+        .collect();
+    assert_eq!(stunned, vec![(1, 2)]);
+    assert_eq!(positions(&loaded), vec![1, 3]);
+}
+
+/** Components declared without the storage and mapping types in scope */
+mod scoped {
+    use gallium::{Component, Deserialize, EntityId, Serialize};
+
+    #[gallium::component(storage = "sparse_set")]
+    pub struct Marker;
+
+    #[gallium::component(map_entities)]
+    pub struct Target(pub EntityId);
+}
+
+#[test]
+fn components_dont_need_the_storage_types_in_scope() {
+    let mut scene = Scene::new();
+    let target = scene.add_entity(EntityBuilder::new().build());
+    let id = scene.add_entity(
+        EntityBuilder::new()
+            .with(scoped::Marker)
+            .with(scoped::Target(target))
+            .build(),
+    );
+    let entity = scene.get_entity(id).unwrap();
+    assert_eq!(entity.get_component::<scoped::Target>().unwrap().0, target);
+    assert!(entity.has_component::<scoped::Marker>());
+}
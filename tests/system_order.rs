@@ -0,0 +1,116 @@
+use gallium::*;
+
+/** Does nothing, only ordered */
+#[derive(Serialize, Deserialize)]
+struct Render;
+
+#[gallium::system]
+impl System for Render {}
+
+/** Does nothing, only ordered */
+#[derive(Serialize, Deserialize)]
+struct Physics;
+
+#[gallium::system]
+impl System for Physics {}
+
+/** Does nothing, only ordered */
+#[derive(Serialize, Deserialize)]
+struct Input;
+
+#[gallium::system]
+impl System for Input {}
+
+/** Construct a scene whose systems are added out of order */
+fn scene() -> Scene {
+    return SceneBuilder::new()
+        .with_system("update", Render)
+        .with_ordered_system(
+            "update",
+            Physics,
+            SystemOrder::new().label("physics").before("Render"),
+        )
+        .with_system("update", Input)
+        .with_ordered_system(
+            "late",
+            Input,
+            SystemOrder::new().label("late_input").before("physics"),
+        )
+        .build();
+}
+
+#[test]
+fn systems_run_after_what_they_depend_on() {
+    let scene = scene();
+    // Input is ready first, physics waits for the late input even though it is in another tag
+    assert_eq!(
+        scene.system_labels("update"),
+        vec!["Input", "physics", "Render"]
+    );
+    assert_eq!(scene.system_labels("late"), vec!["late_input"]);
+}
+
+#[test]
+fn the_order_survives_a_ron_round_trip() {
+    let loaded = Scene::from_ron(scene().to_ron().unwrap()).unwrap();
+    assert_eq!(
+        loaded.system_labels("update"),
+        vec!["Input", "physics", "Render"]
+    );
+}
+
+#[test]
+#[should_panic(expected = "System order contains a cycle: Render -> Input -> Physics -> Render")]
+fn cycles_are_rejected() {
+    SceneBuilder::new()
+        .with_ordered_system("update", Render, SystemOrder::new().after("Physics"))
+        .with_ordered_system("update", Physics, SystemOrder::new().after("Input"))
+        .with_ordered_system("update", Input, SystemOrder::new().after("Render"))
+        .build();
+}
+
+#[test]
+fn systems_making_a_cycle_are_not_added() {
+    let mut scene = SceneBuilder::new()
+        .with_ordered_system("update", Render, SystemOrder::new().after("Physics"))
+        .with_system("update", Physics)
+        .build();
+    let error = scene
+        .add_ordered_system(
+            "input",
+            Input,
+            SystemOrder::new().after("Render").before("Physics"),
+        )
+        .unwrap_err();
+    assert_eq!(
+        error,
+        "System order contains a cycle: Render -> Input -> Physics -> Render"
+    );
+
+    // The scene keeps the systems and order it had
+    assert!(!scene.has_systems("input"));
+    assert_eq!(scene.system_labels("update"), vec!["Physics", "Render"]);
+    scene
+        .add_ordered_system("input", Input, SystemOrder::new().before("Physics"))
+        .unwrap();
+    assert_eq!(scene.system_labels("input"), vec!["Input"]);
+}
+
+#[test]
+fn unknown_labels_are_rejected() {
+    let error = SceneBuilder::new()
+        .with_ordered_system("update", Render, SystemOrder::new().after("physics"))
+        .try_build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        error,
+        "System Render is ordered against label physics, but no system has that label"
+    );
+
+    let mut scene = Scene::new();
+    assert!(scene
+        .add_ordered_system("update", Input, SystemOrder::new().before("Physics"))
+        .is_err());
+    assert!(!scene.has_systems("update"));
+}